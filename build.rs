@@ -38,6 +38,10 @@ struct OutputField {
     field_type: String,
     id: bool,
     read_only: bool,
+    /// Targets after `->`, e.g. `/interface/bridge:name` or `/interface/*:name`
+    references: Vec<String>,
+    /// Range after `@`, e.g. `7..` for fields added in RouterOS 7
+    versions: Option<String>,
 }
 
 fn main() -> std::io::Result<()> {
@@ -53,48 +57,43 @@ fn main() -> std::io::Result<()> {
     {
         let src_file = BufReader::new(File::open(&src_filename)?);
         let mut line_iter = src_file.lines();
-        loop {
-            match line_iter.next() {
-                Some(Ok(line)) => {
-                    if line.starts_with("/") {
-                        let mut current_module = &mut root_module;
-                        for part in part_pattern.find_iter(line.as_str()) {
-                            let match_str = part.as_str();
-                            if match_str.len() < 2 {
-                                continue;
-                            }
-                            let comp_name: String = match_str.chars().skip(1).collect();
-                            current_module = current_module
-                                .sub_modules
-                                .entry(comp_name)
-                                .or_insert(OutputModule::new());
-                        }
-                        open_module = Some(current_module);
-                    } else if line.starts_with("1/") {
-                        let mut current_module = &mut root_module;
-                        for part in part_pattern.find_iter(&line.as_str()[1..]) {
-                            let match_str = part.as_str();
-                            if match_str.len() < 2 {
-                                continue;
-                            }
-                            let comp_name: String = match_str.chars().skip(1).collect();
-                            current_module = current_module
-                                .sub_modules
-                                .entry(comp_name)
-                                .or_insert(OutputModule::new());
-                            //current_module.single_value = true;
-                        }
-                        open_module = Some(current_module);
-                    } else if let Some(current_module) = &mut open_module {
-                        if let Some((field, optional_enum)) = parse_field_line(line) {
-                            if let Some(e) = optional_enum {
-                                current_module.enums.insert(e.type_name.clone(), e);
-                            }
-                            current_module.content.push(field);
-                        }
+        while let Some(Ok(line)) = line_iter.next() {
+            if line.starts_with('/') {
+                let mut current_module = &mut root_module;
+                for part in part_pattern.find_iter(line.as_str()) {
+                    let match_str = part.as_str();
+                    if match_str.len() < 2 {
+                        continue;
                     }
+                    let comp_name: String = match_str.chars().skip(1).collect();
+                    current_module = current_module
+                        .sub_modules
+                        .entry(comp_name)
+                        .or_insert(OutputModule::new());
+                }
+                open_module = Some(current_module);
+            } else if line.starts_with("1/") {
+                let mut current_module = &mut root_module;
+                for part in part_pattern.find_iter(&line.as_str()[1..]) {
+                    let match_str = part.as_str();
+                    if match_str.len() < 2 {
+                        continue;
+                    }
+                    let comp_name: String = match_str.chars().skip(1).collect();
+                    current_module = current_module
+                        .sub_modules
+                        .entry(comp_name)
+                        .or_insert(OutputModule::new());
+                    //current_module.single_value = true;
+                }
+                open_module = Some(current_module);
+            } else if let Some(current_module) = &mut open_module {
+                if let Some((field, optional_enum)) = parse_field_line(line) {
+                    if let Some(e) = optional_enum {
+                        current_module.enums.insert(e.type_name.clone(), e);
+                    }
+                    current_module.content.push(field);
                 }
-                _ => break,
             }
         }
     }
@@ -111,7 +110,7 @@ fn main() -> std::io::Result<()> {
         create_storage(&mut write_handle, &root_module, &vec![], "")?;
         writeln!(write_handle, "  }}")?;
     */
-    dump_module(&mut write_handle, &root_module, 1, &vec![], "")?;
+    dump_module(&mut write_handle, &root_module, &root_module, 1, &[], "")?;
     writeln!(write_handle, "}}")?;
     println!("cargo:rerun-if-changed=generated.rs");
     std::io::Result::Ok(())
@@ -144,47 +143,57 @@ fn create_storage(
 */
 fn dump_module(
     file: &mut BufWriter<File>,
+    root_module: &OutputModule,
     module_data: &OutputModule,
     depth: u8,
-    parent_path: &Vec<&str>,
+    parent_path: &[&str],
     module_name: &str,
 ) -> std::io::Result<()> {
     if module_data.sub_modules.is_empty() && module_data.content.is_empty() {
         return Ok(());
     }
-    let mut module_path = parent_path.clone();
+    let mut module_path = parent_path.to_vec();
     if !module_name.is_empty() {
         module_path.push(module_name);
     }
 
     let prefix = "  ".repeat(depth.into());
     if !module_data.content.is_empty() {
-        //writeln!(file, "{prefix}use ros_macro::RouterOsApiFieldAccess;")?;
         writeln!(file, "{prefix}use crate::RosError;")?;
         writeln!(
             file,
             "{prefix}use crate::model::ros_value::{{Auto, ValueFormat, IpWithInterface, IpOrInterface}};"
         )?;
-        writeln!(file, "{prefix}use crate::model::FieldDescription;")?;
         writeln!(file, "{prefix}use ipnet::IpNet;")?;
         writeln!(file, "{prefix}use mac_address::MacAddress;")?;
         writeln!(file, "{prefix}use std::collections::HashSet;")?;
         writeln!(file, "{prefix}use std::ops::RangeInclusive;")?;
         writeln!(file, "{prefix}use std::net::IpAddr;")?;
         writeln!(file, "{prefix}use std::time::Duration;")?;
-        let model_name = module_path[1..].join("-").to_case(Case::UpperCamel);
+        // `ip/address` becomes `Address`, menus at the top keep their name
+        let name_parts = match module_path.len() {
+            1 => &module_path[..],
+            _ => &module_path[1..],
+        };
+        let model_name = name_parts.join("-").to_case(Case::UpperCamel);
         for (type_name, type_values) in module_data.enums.iter() {
-            writeln!(file, "{prefix}#[derive(Debug, Eq, PartialEq, Clone, Hash)]")?;
+            writeln!(
+                file,
+                "{prefix}#[derive(Debug, Eq, PartialEq, Clone, Hash, Default)]"
+            )?;
             writeln!(file, "{prefix}#[allow(non_camel_case_types)]")?;
             writeln!(file, "{prefix}pub enum {type_name} {{")?;
+            let default_value =
+                expand_enum_name(type_values.values.first().unwrap().as_str()).unwrap();
             for value in type_values.values.iter() {
                 if let Some(enum_value) = expand_enum_name(value.as_str()) {
+                    if enum_value == default_value {
+                        writeln!(file, "{prefix}  #[default]")?;
+                    }
                     writeln!(file, "{prefix}  {enum_value},")?;
                 }
             }
             writeln!(file, "{prefix}}}")?;
-            let default_value =
-                expand_enum_name(type_values.values.iter().next().unwrap().as_str()).unwrap();
             writeln!(
                 file,
                 "{prefix}impl crate::model::RosValue for {type_name} {{"
@@ -227,106 +236,56 @@ fn dump_module(
             writeln!(file, "{prefix}    }})")?;
             writeln!(file, "{prefix}  }}")?;
             writeln!(file, "{prefix}}}")?;
-            writeln!(file, "{prefix}impl Default for {type_name} {{")?;
-            writeln!(file, "{prefix}  fn default() -> Self {{")?;
-            writeln!(file, "{prefix}    {type_name}::{default_value}")?;
-            writeln!(file, "{prefix}  }}")?;
-            writeln!(file, "{prefix}}}")?;
         }
 
-        for field in module_data.content.iter() {
-            let fd_name = field_description_name(&field.field_name);
-            writeln!(file, "{prefix}const {fd_name}:crate::model::FieldDescription=crate::model::FieldDescription{{")?;
-            writeln!(file, "{prefix}  name:\"{}\",", field.field_name)?;
-            writeln!(file, "{prefix}  is_read_only:{},", field.read_only)?;
-            writeln!(file, "{prefix}  is_id:{},", field.id)?;
-            writeln!(file, "{prefix}}};")?;
-        }
-        writeln!(file, "{prefix}#[derive(Debug, Default, Clone)]")?;
+        writeln!(
+            file,
+            "{prefix}#[derive(Debug, Default, Clone, crate::model::RouterOsResource)]"
+        )?;
+        writeln!(file, "{prefix}#[ros(path = \"{}\")]", module_path.join("/"))?;
         writeln!(file, "{prefix}pub struct {model_name} {{")?;
-        //let mut has_id = false;
         for field in module_data.content.iter() {
             let field_name = expand_field_name(&field.field_name);
-            let is_id = field.field_name == ".id";
-            //has_id |= is_id;
-            let access = if is_id { "" } else { "pub " };
+            let mut attributes = Vec::new();
+            if field_name.trim_start_matches("r#").replace('_', "-") != field.field_name {
+                attributes.push(format!("name = \"{}\"", field.field_name));
+            }
+            if field.id {
+                attributes.push(String::from("key"));
+            }
+            if field.read_only {
+                attributes.push(String::from("read_only"));
+            }
+            if field.field_name == "dynamic" {
+                attributes.push(String::from("dynamic"));
+            }
+            for target in field.references.iter() {
+                for (path, target_field) in expand_reference(root_module, target) {
+                    attributes.push(format!("reference = \"{path}:{target_field}\""));
+                }
+            }
+            if let Some(versions) = &field.versions {
+                attributes.push(format!("versions = \"{versions}\""));
+            }
+            if !attributes.is_empty() {
+                writeln!(file, "{prefix}  #[ros({})]", attributes.join(", "))?;
+            }
+            let access = if field.field_name == ".id" {
+                ""
+            } else {
+                "pub "
+            };
             writeln!(
                 file,
                 "{prefix}  {access}{field_name}: crate::model::RosFieldValue<{field_type}>,",
                 field_type = field.field_type
             )?;
         }
-        writeln!(file, "{prefix}}}")?;
-        //let field_name = module_path[1..].join("-").to_case(Case::Snake);
-        let module_path = module_path.join("/");
-        writeln!(
-            file,
-            "{prefix}impl crate::model::RouterOsResource for {model_name} {{"
-        )?;
-        writeln!(file, "{prefix}   fn resource_path() -> &'static str {{")?;
-        writeln!(file, "{prefix}     \"{module_path}\"")?;
-        writeln!(file, "{prefix}    }}")?;
-
-        writeln!(file, "{prefix}}}")?;
-
-        writeln!(
-            file,
-            "{prefix}impl crate::model::{variant} for {model_name} {{",
-            variant = if module_data.content.iter().any(|f| f.id) {
-                "RouterOsListResource"
-            } else {
-                "RouterOsSingleResource"
-            }
-        )?;
-        writeln!(file, "{prefix}}}")?;
-
-        writeln!(
-            file,
-            "{prefix}impl crate::model::RouterOsApiFieldAccess for {model_name} {{"
-        )?;
-        writeln!(file, "{prefix}  fn fields_mut(&mut self) -> Box<dyn Iterator<Item = (&'static crate::model::FieldDescription, &mut dyn crate::model::RosFieldAccessor)> + '_> {{")?;
-
-        writeln!(
-            file,
-            "{prefix}    let fields: Vec<(&'static crate::model::FieldDescription, &mut dyn crate::model::RosFieldAccessor)> = vec!["
-        )?;
-        for field in module_data.content.iter() {
-            let field_name_rust = expand_field_name(&field.field_name);
-            let fd_name = field_description_name(&field.field_name);
-            writeln!(
-                file,
-                "{prefix}      (&{fd_name}, &mut self.{field_name_rust}),"
-            )?;
-        }
-        writeln!(file, "{prefix}    ];")?;
-        writeln!(file, "{prefix}    Box::new(fields.into_iter())")?;
-        writeln!(file, "{prefix}  }}")?;
-
-        writeln!(file, "{prefix}  fn fields(&self) -> Box<dyn Iterator<Item = (&'static crate::model::FieldDescription, &dyn crate::model::RosFieldAccessor)> + '_> {{")?;
-
+        writeln!(file, "{prefix}  #[ros(extra)]")?;
         writeln!(
             file,
-            "{prefix}    let fields: Vec<(&'static crate::model::FieldDescription, &dyn crate::model::RosFieldAccessor)> = vec!["
+            "{prefix}  pub extra_attributes: crate::model::extra::ExtraAttributes,"
         )?;
-        for field in module_data.content.iter() {
-            let field_name_rust = expand_field_name(&field.field_name);
-            let fd_name = field_description_name(&field.field_name);
-            writeln!(file, "{prefix}      (&{fd_name}, &self.{field_name_rust}),")?;
-        }
-        writeln!(file, "{prefix}   ];")?;
-        writeln!(file, "{prefix}   Box::new(fields.into_iter())")?;
-        writeln!(file, "{prefix}  }}")?;
-
-        if let Some(_) = module_data
-            .content
-            .iter()
-            .find(|t| &t.field_name == "dynamic")
-        {
-            writeln!(file, "{prefix}  fn is_dynamic(&self) -> bool {{")?;
-            writeln!(file, "{prefix}   self.dynamic.get().unwrap_or(false)")?;
-            writeln!(file, "{prefix}  }}")?;
-        }
-
         writeln!(file, "{prefix}}}")?;
     }
     for (module_name, module_data) in module_data.sub_modules.iter() {
@@ -335,19 +294,67 @@ fn dump_module(
             "{prefix}pub mod {module_name} {{",
             module_name = expand_field_name(module_name)
         )?;
-        dump_module(file, module_data, depth + 1, &module_path, module_name)?;
+        dump_module(
+            file,
+            root_module,
+            module_data,
+            depth + 1,
+            &module_path,
+            module_name,
+        )?;
         writeln!(file, "{prefix}}}")?;
     }
     Ok(())
 }
 
+/// Menus and fields a reference target like `/interface/*:name` stands for, `*` matches
+/// every menu directly below that has the field and the menu itself if it lists the entries
+/// of all of them, like `/interface` does
+fn expand_reference(root_module: &OutputModule, target: &str) -> Vec<(String, String)> {
+    let (path, field) = target
+        .split_once(':')
+        .unwrap_or_else(|| panic!("Reference without field: {target}"));
+    let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+    let (prefix, wildcard) = match parts.split_last() {
+        Some((&"*", prefix)) => (prefix, true),
+        _ => (parts.as_slice(), false),
+    };
+    let mut module = root_module;
+    for part in prefix {
+        module = module
+            .sub_modules
+            .get(*part)
+            .unwrap_or_else(|| panic!("Unknown menu in reference: {target}"));
+    }
+    let candidates: Vec<(String, &OutputModule)> = if wildcard {
+        module
+            .sub_modules
+            .iter()
+            .map(|(name, sub_module)| (format!("{}/{name}", prefix.join("/")), sub_module))
+            .chain([(prefix.join("/"), module)])
+            .collect()
+    } else {
+        vec![(prefix.join("/"), module)]
+    };
+    let mut ret: Vec<(String, String)> = candidates
+        .into_iter()
+        .filter(|(_, module)| module.content.iter().any(|f| f.field_name == field))
+        .map(|(path, _)| (path, field.to_owned()))
+        .collect();
+    if ret.is_empty() {
+        panic!("Reference matches no field: {target}");
+    }
+    ret.sort();
+    ret
+}
+
 fn expand_enum_name(name: &str) -> Option<String> {
     Some(name2rust(name, true)).filter(|v| !v.is_empty())
 }
 
 fn expand_field_name(name: &str) -> String {
     let field_name = name2rust(name, false).to_case(Case::Snake);
-    if field_name == "static" {
+    if matches!(field_name.as_str(), "static" | "type") {
         format!("r#{field_name}")
     } else {
         field_name
@@ -355,8 +362,8 @@ fn expand_field_name(name: &str) -> String {
 }
 
 fn name2rust(string: &str, start_capital: bool) -> String {
-    if string.chars().next() == Some('.') {
-        return name2rust(&string[1..], start_capital);
+    if let Some(stripped) = string.strip_prefix('.') {
+        return name2rust(stripped, start_capital);
     }
     let mut result = String::new();
     let mut last_skipped = false;
@@ -368,7 +375,7 @@ fn name2rust(string: &str, start_capital: bool) -> String {
                 result.push(ch.to_ascii_lowercase());
             }
             last_skipped = false;
-        } else if ch.is_digit(10) {
+        } else if ch.is_ascii_digit() {
             if last_skipped || result.is_empty() {
                 result.push('_');
             }
@@ -382,6 +389,22 @@ fn name2rust(string: &str, start_capital: bool) -> String {
 }
 
 fn parse_field_line(line: String) -> Option<(OutputField, Option<Enum>)> {
+    let (line, references) = match line.split_once("->") {
+        Some((line, targets)) => (
+            line,
+            targets
+                .split(',')
+                .map(str::trim)
+                .filter(|target| !target.is_empty())
+                .map(String::from)
+                .collect(),
+        ),
+        None => (line.as_str(), Vec::new()),
+    };
+    let (line, versions) = match line.split_once('@') {
+        Some((line, versions)) => (line, Some(versions.trim().to_owned())),
+        None => (line, None),
+    };
     let mut chars = line.chars();
     let mut field_name = String::new();
     let mut is_id = false;
@@ -389,7 +412,7 @@ fn parse_field_line(line: String) -> Option<(OutputField, Option<Enum>)> {
     loop {
         match chars.next() {
             None => break,
-            Some(ch) if ch == ':' => break,
+            Some(':') => break,
             Some('*') if field_name.is_empty() => is_id = true,
             Some('!') if field_name.is_empty() => is_read_only = true,
             Some(ch) if !ch.is_whitespace() => field_name.push(ch),
@@ -399,7 +422,7 @@ fn parse_field_line(line: String) -> Option<(OutputField, Option<Enum>)> {
     //let mut closure_field_type = field_type.clone();
     let push_to_components = |field_type_components: &mut Vec<String>, field_type: &mut String| {
         let striped_field_type = String::from(field_type.trim());
-        if striped_field_type.len() > 0 {
+        if !striped_field_type.is_empty() {
             field_type_components.push(striped_field_type);
         }
         field_type.clear();
@@ -433,11 +456,13 @@ fn parse_field_line(line: String) -> Option<(OutputField, Option<Enum>)> {
                 field_type: String::from("String"),
                 id: is_id,
                 read_only: is_read_only,
+                references,
+                versions,
             },
             None,
         ));
     }
-    return if field_type_components.len() == 1 {
+    if field_type_components.len() == 1 {
         let trimmed_type = field_type_components.remove(0);
         Some((
             OutputField {
@@ -445,6 +470,8 @@ fn parse_field_line(line: String) -> Option<(OutputField, Option<Enum>)> {
                 field_type: trimmed_type,
                 id: is_id,
                 read_only: is_read_only,
+                references,
+                versions,
             },
             None,
         ))
@@ -460,20 +487,13 @@ fn parse_field_line(line: String) -> Option<(OutputField, Option<Enum>)> {
                 field_type: field_type_name,
                 id: is_id,
                 read_only: is_read_only,
+                references,
+                versions,
             },
             Some(Enum {
                 type_name: name2rust(trimmed_name, true),
                 values: field_type_components,
             }),
         ))
-    };
-}
-
-fn field_description_name(field_name: &str) -> String {
-    format!(
-        "{}_FIELD_DESCRIPTION",
-        name2rust(field_name, false)
-            .to_case(Case::Snake)
-            .to_ascii_uppercase()
-    )
+    }
 }
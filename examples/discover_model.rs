@@ -0,0 +1,31 @@
+//! Prints the `ros_model` description of a menu tree as reported by a router.
+//!
+//! `cargo run --example discover_model -- 192.168.88.1 admin password /ip/firewall > ros_model/firewall.txt`
+
+use std::env;
+use std::net::{IpAddr, SocketAddr};
+
+use routeros::client::api::{ApiClient, Transport};
+use routeros::RosError;
+
+#[tokio::main]
+async fn main() -> Result<(), RosError> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let [target, username, password, menu] = args.as_slice() else {
+        eprintln!("usage: discover_model <address> <username> <password> <menu>");
+        std::process::exit(2);
+    };
+    let target: IpAddr = target.parse()?;
+    let transport = Transport::Plain;
+    let client = ApiClient::connect(
+        SocketAddr::new(target, transport.default_port()),
+        username.clone(),
+        password.clone(),
+        transport,
+    )
+    .await?;
+    let menus = client.discover(menu).await?;
+    let model: Vec<String> = menus.iter().map(ToString::to_string).collect();
+    print!("{}", model.join("\n"));
+    Ok(())
+}
@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use routeros::client::api::ApiWord;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(Some(word)) = ApiWord::parse(data) {
+        assert_eq!(ApiWord::parse(&word.encode()).unwrap(), Some(word));
+    }
+});
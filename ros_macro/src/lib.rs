@@ -0,0 +1,322 @@
+//! Derive macro for the resource traits of the `routeros` crate.
+//!
+//! ```ignore
+//! use routeros::model::{RosFieldValue, RouterOsResource};
+//!
+//! #[derive(Debug, Default, Clone, RouterOsResource)]
+//! #[ros(path = "ip/pool")]
+//! pub struct Pool {
+//!     #[ros(key, name = ".id")]
+//!     id: RosFieldValue<String>,
+//!     #[ros(key)]
+//!     pub name: RosFieldValue<String>,
+//!     pub ranges: RosFieldValue<String>,
+//!     #[ros(read_only)]
+//!     pub total: RosFieldValue<u64>,
+//! }
+//! ```
+//!
+//! Resources with a key field are list resources, all others single resources.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, Ident, LitStr};
+
+/// Implements `RouterOsResource`, `RouterOsApiFieldAccess` and either `RouterOsListResource`
+/// or `RouterOsSingleResource` for a struct of `RosFieldValue` fields.
+///
+/// The struct takes `#[ros(path = "interface/bridge")]`, each field optionally
+/// - `name = "use-peer-dns"`: attribute name on the router, by default the field name with
+///   `-` instead of `_`
+/// - `key`: identifies the entry, makes the struct a list resource
+/// - `read_only`: only reported by the router, never sent
+/// - `dynamic`: `bool` field that marks entries the router created itself
+/// - `reference = "interface/bridge:name"`: the value is the `name` of an entry of
+///   `interface/bridge`, may be given multiple times
+/// - `versions = "6.41..7"`: RouterOS versions with the field, from the first one up to but
+///   excluding the second, either bound may be left out as in `"7.."`
+///
+/// A single `ExtraAttributes` field marked `#[ros(extra)]` keeps the attributes lenient
+/// parsing could not read into the other fields.
+#[proc_macro_derive(RouterOsResource, attributes(ros))]
+pub fn derive_router_os_resource(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    ident: Ident,
+    name: String,
+    key: bool,
+    read_only: bool,
+    dynamic: bool,
+    references: Vec<(String, String)>,
+    since: Option<Version>,
+    until: Option<Version>,
+    extra: bool,
+}
+
+/// Major, minor and patch of a RouterOS version
+type Version = [u16; 3];
+
+/// Version like `7.1` with missing parts as `0`
+fn parse_version(value: &str) -> Option<Version> {
+    let mut ret = [0; 3];
+    let parts: Vec<&str> = value.trim().split('.').collect();
+    if parts.len() > ret.len() {
+        return None;
+    }
+    for (target, part) in ret.iter_mut().zip(parts) {
+        *target = part.parse().ok()?;
+    }
+    Some(ret)
+}
+
+/// Bounds of a range like `6.41..7`, `7..` or `..7`
+fn parse_versions(range: &LitStr) -> syn::Result<(Option<Version>, Option<Version>)> {
+    let invalid = || {
+        Error::new(
+            range.span(),
+            "expected versions like \"6.41..7\" or \"7..\"",
+        )
+    };
+    let value = range.value();
+    let (since, until) = value.split_once("..").ok_or_else(invalid)?;
+    let bound = |bound: &str| match bound.trim() {
+        "" => Ok(None),
+        bound => parse_version(bound).map(Some).ok_or_else(invalid),
+    };
+    Ok((bound(since)?, bound(until)?))
+}
+
+fn version_tokens(version: Option<Version>) -> TokenStream2 {
+    match version {
+        Some([major, minor, patch]) => quote! {
+            ::std::option::Option::Some(::routeros::model::version::RosVersion::new(#major, #minor, #patch))
+        },
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
+fn resource_path(input: &DeriveInput) -> syn::Result<String> {
+    let mut path = None;
+    for attribute in input.attrs.iter().filter(|a| a.path().is_ident("ros")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("path") {
+                let value: LitStr = meta.value()?.parse()?;
+                path = Some(value.value().trim_matches('/').to_owned());
+                Ok(())
+            } else {
+                Err(meta.error("unknown resource attribute"))
+            }
+        })?;
+    }
+    path.ok_or_else(|| Error::new(input.ident.span(), "missing #[ros(path = \"...\")]"))
+}
+
+fn parse_field(field: &syn::Field) -> syn::Result<Field> {
+    let ident = field
+        .ident
+        .clone()
+        .ok_or_else(|| Error::new(field.span(), "fields must be named"))?;
+    let mut ret = Field {
+        name: ident.to_string().trim_start_matches("r#").replace('_', "-"),
+        ident,
+        key: false,
+        read_only: false,
+        dynamic: false,
+        references: Vec::new(),
+        since: None,
+        until: None,
+        extra: false,
+    };
+    for attribute in field.attrs.iter().filter(|a| a.path().is_ident("ros")) {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                ret.name = meta.value()?.parse::<LitStr>()?.value();
+            } else if meta.path.is_ident("key") {
+                ret.key = true;
+            } else if meta.path.is_ident("read_only") {
+                ret.read_only = true;
+            } else if meta.path.is_ident("dynamic") {
+                ret.dynamic = true;
+            } else if meta.path.is_ident("reference") {
+                let value: LitStr = meta.value()?.parse()?;
+                let target = value.value();
+                let (path, field) = target.split_once(':').ok_or_else(|| {
+                    Error::new(
+                        value.span(),
+                        "expected a reference like \"menu/path:field\"",
+                    )
+                })?;
+                ret.references
+                    .push((path.trim_matches('/').to_owned(), field.to_owned()));
+            } else if meta.path.is_ident("extra") {
+                ret.extra = true;
+            } else if meta.path.is_ident("versions") {
+                (ret.since, ret.until) = parse_versions(&meta.value()?.parse()?)?;
+            } else {
+                return Err(meta.error("unknown field attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let path = resource_path(input)?;
+    let named = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(named) => &named.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "expected a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "expected a struct with named fields",
+            ))
+        }
+    };
+    let (extras, fields): (Vec<Field>, Vec<Field>) = named
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?
+        .into_iter()
+        .partition(|field| field.extra);
+    if let Some(second) = extras.get(1) {
+        return Err(Error::new(
+            second.ident.span(),
+            "only one field can keep extra attributes",
+        ));
+    }
+    let mut dynamic = fields.iter().filter(|field| field.dynamic);
+    let dynamic_field = dynamic.next().map(|field| &field.ident);
+    if let Some(second) = dynamic.next() {
+        return Err(Error::new(
+            second.ident.span(),
+            "only one field can be dynamic",
+        ));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let count = fields.len();
+    let descriptions = fields.iter().map(|field| {
+        let Field {
+            name,
+            key,
+            read_only,
+            references,
+            since,
+            until,
+            ..
+        } = field;
+        let since = version_tokens(*since);
+        let until = version_tokens(*until);
+        let references = references.iter().map(|(path, field)| {
+            quote! { ::routeros::model::FieldReference { path: #path, field: #field } }
+        });
+        quote! {
+            ::routeros::model::FieldDescription {
+                name: #name,
+                is_read_only: #read_only,
+                is_id: #key,
+                references: &[#(#references),*],
+                since: #since,
+                until: #until,
+            }
+        }
+    });
+    let indices: Vec<usize> = (0..count).collect();
+    let idents: Vec<&Ident> = fields.iter().map(|field| &field.ident).collect();
+    let variant = if fields.iter().any(|field| field.key) {
+        quote! { RouterOsListResource }
+    } else {
+        quote! { RouterOsSingleResource }
+    };
+    let is_dynamic = dynamic_field.map(|field| {
+        quote! {
+            fn is_dynamic(&self) -> bool {
+                self.#field.get().unwrap_or(false)
+            }
+        }
+    });
+    let extra_attributes = extras.first().map(|field| {
+        let field = &field.ident;
+        quote! {
+            fn extra_attributes(&self) -> ::std::option::Option<&::routeros::model::extra::ExtraAttributes> {
+                ::std::option::Option::Some(&self.#field)
+            }
+
+            fn extra_attributes_mut(
+                &mut self,
+            ) -> ::std::option::Option<&mut ::routeros::model::extra::ExtraAttributes> {
+                ::std::option::Option::Some(&mut self.#field)
+            }
+        }
+    });
+
+    Ok(quote! {
+        const _: () = {
+            static FIELDS: [::routeros::model::FieldDescription; #count] = [#(#descriptions),*];
+
+            impl #impl_generics ::routeros::model::RouterOsResource for #ident #type_generics #where_clause {
+                fn resource_path() -> &'static str {
+                    #path
+                }
+            }
+
+            impl #impl_generics ::routeros::model::#variant for #ident #type_generics #where_clause {}
+
+            impl #impl_generics ::routeros::model::RouterOsApiFieldAccess for #ident #type_generics #where_clause {
+                fn fields_mut(
+                    &mut self,
+                ) -> ::std::boxed::Box<
+                    dyn ::std::iter::Iterator<
+                            Item = (
+                                &'static ::routeros::model::FieldDescription,
+                                &mut dyn ::routeros::model::RosFieldAccessor,
+                            ),
+                        > + '_,
+                > {
+                    let fields: ::std::vec::Vec<(
+                        &'static ::routeros::model::FieldDescription,
+                        &mut dyn ::routeros::model::RosFieldAccessor,
+                    )> = ::std::vec![#((&FIELDS[#indices], &mut self.#idents)),*];
+                    ::std::boxed::Box::new(fields.into_iter())
+                }
+
+                fn fields(
+                    &self,
+                ) -> ::std::boxed::Box<
+                    dyn ::std::iter::Iterator<
+                            Item = (
+                                &'static ::routeros::model::FieldDescription,
+                                &dyn ::routeros::model::RosFieldAccessor,
+                            ),
+                        > + '_,
+                > {
+                    let fields: ::std::vec::Vec<(
+                        &'static ::routeros::model::FieldDescription,
+                        &dyn ::routeros::model::RosFieldAccessor,
+                    )> = ::std::vec![#((&FIELDS[#indices], &self.#idents)),*];
+                    ::std::boxed::Box::new(fields.into_iter())
+                }
+
+                #is_dynamic
+
+                #extra_attributes
+            }
+        };
+    })
+}
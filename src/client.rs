@@ -7,6 +7,13 @@ use std::slice::{Iter, IterMut};
 use async_trait::async_trait;
 use field_ref::FieldRef;
 
+use crate::client::command::{Command, CommandOutput};
+use crate::client::filter::{Filter, Projection};
+use crate::client::journal::JournalingClient;
+use crate::client::ordered::OrderedListAccess;
+use crate::client::plan::{ChangePlan, EntryRef, PlannedChange, Removal};
+use crate::model::dynamic::{DynamicMenu, DynamicResource};
+use crate::model::extra::ParseWarning;
 use crate::model::RosFieldValue;
 use crate::model::RosValue;
 use crate::model::RouterOsListResource;
@@ -15,9 +22,22 @@ use crate::model::RouterOsSingleResource;
 use crate::RosError;
 
 pub mod api;
+pub mod command;
 pub mod config;
+pub mod filter;
+pub mod journal;
+pub mod ordered;
+pub mod plan;
+pub mod reconnect;
+pub mod references;
+pub mod rest;
 
 pub mod supplier;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod tls;
+pub mod transaction;
+pub mod version;
 
 #[async_trait]
 pub trait Client: Send + Sync {
@@ -39,32 +59,139 @@ pub trait Client: Send + Sync {
             remove_if_not_touched: Vec::new(),
         })
     }
+    /// Fetches the entries of a menu whose order matters, see [`OrderedListAccess`]
+    async fn fetch_ordered<Resource>(&mut self) -> Result<OrderedListAccess<Resource>, RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let fetched_data: Vec<Resource> =
+            self.list()
+                .await
+                .map_err(|error| RosError::StructureAccessError {
+                    structure: Resource::resource_path(),
+                    error: Box::new(error),
+                })?;
+        Ok(OrderedListAccess::new(fetched_data))
+    }
     async fn get<Resource>(&mut self) -> Result<ResourceSingleAccess<Resource>, RosError>
     where
         Resource: RouterOsSingleResource,
     {
-        let value = if let Some(value) = self.list().await?.into_iter().next() {
-            value
-        } else {
-            Resource::default()
-        };
+        let value = self.list().await?.into_iter().next().unwrap_or_default();
         Ok(ResourceSingleAccess { data: value })
     }
     async fn list<Resource>(&mut self) -> Result<Vec<Resource>, RosError>
     where
         Resource: RouterOsResource;
+    /// Lists the entries matching `filter`, clients without server side queries
+    /// filter the complete list
+    async fn list_where<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        filter.check()?;
+        let entries: Vec<Resource> = self.list().await?;
+        Ok(entries
+            .into_iter()
+            .filter(|entry| filter.matches(entry))
+            .collect())
+    }
+    /// Lists the entries matching `filter` with only the fields of `projection` read
+    async fn list_projected<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+        projection: &Projection<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        projection.check()?;
+        let entries: Vec<Resource> = self.list_where(filter).await?;
+        entries
+            .iter()
+            .map(|entry| projection.apply(entry))
+            .collect()
+    }
     async fn update<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
     where
         Resource: RouterOsListResource;
     async fn set<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
     where
         Resource: RouterOsSingleResource;
-    async fn add<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    /// Adds `resource` and writes the `.id` assigned to it back into the resource.
+    ///
+    /// Returns the new id, or `None` if nothing was added.
+    async fn add<Resource>(&mut self, resource: &mut Resource) -> Result<Option<String>, RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        self.add_before(resource, None).await
+    }
+    /// Adds `resource` in front of the entry with the id `before`, at the end if `None`
+    async fn add_before<Resource>(
+        &mut self,
+        resource: &mut Resource,
+        before: Option<&str>,
+    ) -> Result<Option<String>, RosError>
+    where
+        Resource: RouterOsListResource;
+    /// Moves the existing `resource` in front of the entry with the id `before`, to the
+    /// end if `None`
+    async fn move_before<Resource>(
+        &mut self,
+        resource: Resource,
+        before: Option<&str>,
+    ) -> Result<(), RosError>
     where
         Resource: RouterOsListResource;
     async fn delete<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
     where
         Resource: RouterOsListResource;
+    /// Deletes an entry of a menu whose order matters, [`JournalingClient`] adds it back at
+    /// the same place
+    async fn delete_ordered<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        self.delete(resource).await
+    }
+
+    /// Lists the entries of a menu that is not part of the generated model, clients that
+    /// do not implement the dynamic methods fail
+    async fn list_dynamic(&mut self, menu: &DynamicMenu) -> Result<Vec<DynamicResource>, RosError> {
+        Err(dynamic_unsupported(menu))
+    }
+    /// Sends the modified attributes, entries without a key field are set like single
+    /// resources
+    async fn update_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        Err(dynamic_unsupported(resource.menu()))
+    }
+    async fn add_dynamic(
+        &mut self,
+        resource: &mut DynamicResource,
+    ) -> Result<Option<String>, RosError> {
+        Err(dynamic_unsupported(resource.menu()))
+    }
+    async fn delete_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        Err(dynamic_unsupported(resource.menu()))
+    }
+
+    /// Runs a command like `/system/reboot`, waits until the router reports it as done
+    async fn execute(&mut self, command: &Command) -> Result<CommandOutput, RosError>;
+
+    /// Attributes lenient parsing did not read into fields since the last call, see
+    /// [`crate::model::extra`]
+    fn take_warnings(&mut self) -> Vec<ParseWarning>;
+}
+
+fn dynamic_unsupported(menu: &DynamicMenu) -> RosError {
+    RosError::SimpleMessage(format!(
+        "Client does not support menus outside the model like {}",
+        menu.path()
+    ))
 }
 
 #[derive(Debug, Default)]
@@ -129,6 +256,17 @@ where
     {
         Ok(())
     }
+    fn resource_path(&self) -> &'static str {
+        R::resource_path()
+    }
+    fn referenced_menus(&self) -> Vec<&'static str> {
+        referenced_menus::<R>()
+    }
+    fn plan(&self) -> ChangePlan {
+        let mut plan = ChangePlan::new();
+        plan.extend(PlannedChange::update(&self.data, None));
+        plan
+    }
     async fn rollback<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
     where
         C: Client + 'a,
@@ -143,6 +281,19 @@ where
     }
 }
 
+/// Menus other than its own the fields of `R` refer to
+pub(crate) fn referenced_menus<R: RouterOsResource>() -> Vec<&'static str> {
+    let mut ret: Vec<&'static str> = R::default()
+        .fields()
+        .flat_map(|(description, _)| description.references.iter())
+        .map(|reference| reference.path)
+        .filter(|path| *path != R::resource_path())
+        .collect();
+    ret.sort_unstable();
+    ret.dedup();
+    ret
+}
+
 #[async_trait]
 pub trait ResourceAccess {
     async fn commit_remove<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
@@ -154,6 +305,14 @@ pub trait ResourceAccess {
     async fn commit_add<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
     where
         C: Client + 'a;
+    /// Path of the menu the entries belong to, e.g. `ip/address`
+    fn resource_path(&self) -> &'static str;
+    /// Other menus the entries refer to, declared with `->` in `ros_model`
+    fn referenced_menus(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// Changes `commit` would send, without sending them
+    fn plan(&self) -> ChangePlan;
     async fn commit<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
     where
         C: Client + 'a,
@@ -164,55 +323,38 @@ pub trait ResourceAccess {
         self.rollback(client).await?;
         Ok(())
     }
-    async fn rollback<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
-    where
-        C: Client + 'a;
-}
-
-/*
-#[async_trait]
-impl<I: Iterator<Item = Box<dyn ResourceAccess>>> ResourceAccess for I {
-    async fn commit_remove<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
-    where
-        C: Client + 'a,
-    {
-        for mut r in self.deref_mut() {
-            r.commit_remove(client).await?;
-        }
-        Ok(())
-    }
-
-    async fn commit_update<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
+    /// Commits like [`ResourceAccess::commit`], but if a step fails the changes already
+    /// sent are undone and the error is returned as [`RosError::RolledBack`]
+    async fn commit_transactional<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
     where
         C: Client + 'a,
     {
-        for mut r in self.deref_mut() {
-            r.commit_update(client).await?;
+        let mut journal = JournalingClient::new(client);
+        let result = async {
+            self.commit_remove(&mut journal).await?;
+            self.commit_update(&mut journal).await?;
+            self.commit_add(&mut journal).await
         }
-        Ok(())
-    }
-
-    async fn commit_add<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
-    where
-        C: Client + 'a,
-    {
-        for mut r in self.deref_mut() {
-            r.commit_add(client).await?;
+        .await;
+        match result {
+            Ok(()) => self.rollback(client).await,
+            Err(error) => {
+                let report = journal.undo().await;
+                // the entries in memory may not match the router anymore, a failed reload
+                // leaves them as they are
+                let _ = self.rollback(client).await;
+                Err(RosError::RolledBack {
+                    error: Box::new(error),
+                    report: Box::new(report),
+                })
+            }
         }
-        Ok(())
     }
-
     async fn rollback<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
     where
-        C: Client + 'a,
-    {
-        for mut r in self.deref_mut() {
-            r.rollback(client).await?;
-        }
-        Ok(())
-    }
+        C: Client + 'a;
 }
-*/
+
 impl<R> ResourceListAccess<R>
 where
     R: RouterOsListResource,
@@ -415,17 +557,48 @@ impl<R: RouterOsListResource> ResourceAccess for ResourceListAccess<R> {
     where
         C: Client + 'a,
     {
-        let new_entries: Vec<R> = self
+        // entries without any value set are skipped, there is nothing to add
+        for new_entry in self
             .new_data
-            .iter()
-            .filter(|e| !e.is_dynamic())
-            .map(R::clone)
-            .collect();
-        for new_entry in new_entries {
+            .iter_mut()
+            .filter(|e| !e.is_dynamic() && e.is_modified())
+        {
             client.add(new_entry).await?;
         }
         Ok(())
     }
+    fn resource_path(&self) -> &'static str {
+        R::resource_path()
+    }
+    fn referenced_menus(&self) -> Vec<&'static str> {
+        referenced_menus::<R>()
+    }
+    fn plan(&self) -> ChangePlan {
+        let mut plan = ChangePlan::new();
+        let removals = self
+            .remove_data
+            .iter()
+            .map(|e| (e, Removal::Explicit))
+            .chain(
+                self.remove_if_not_touched
+                    .iter()
+                    .map(|e| (e, Removal::Untouched)),
+            );
+        for (entry, removal) in removals.filter(|(e, _)| !e.is_dynamic()) {
+            plan.push(PlannedChange::delete(entry, removal));
+        }
+        for entry in self.fetched_data.iter().filter(|e| !e.is_dynamic()) {
+            plan.extend(PlannedChange::update(entry, EntryRef::of(entry)));
+        }
+        for entry in self
+            .new_data
+            .iter()
+            .filter(|e| !e.is_dynamic() && e.is_modified())
+        {
+            plan.push(PlannedChange::add(entry, None));
+        }
+        plan
+    }
 
     async fn rollback<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
     where
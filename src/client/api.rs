@@ -1,36 +1,57 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::mem;
+use std::io;
+use std::iter::once;
 use std::net::{IpAddr, SocketAddr};
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt, StreamExt};
+use md5::{Digest, Md5};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio_util::codec::Framed;
 
+use crate::client::api::codec::{MalformedSentence, SentenceCodec};
+use crate::client::command::{Command, CommandOutput};
+use crate::client::filter::{Filter, Projection, Query};
+use crate::client::tls::{TlsConfig, API_SSL_PORT};
+use crate::client::version::{router_version, UnsupportedFields, VersionFilter};
 use crate::client::Client;
+use crate::model::dynamic::{DynamicMenu, DynamicResource};
+use crate::model::extra::{AttributeParser, ParseMode, ParseWarning};
+use crate::model::version::RosVersion;
 use crate::model::{RouterOsListResource, RouterOsResource, RouterOsSingleResource, ValueFormat};
 use crate::RosError;
 
+pub mod codec;
+#[cfg(feature = "fake-server")]
+pub mod fake;
+pub mod inspect;
+pub mod record;
+pub mod stream;
+#[cfg(test)]
+mod tests;
+
+/// Type of a reply sentence
 #[derive(Debug, Clone, Eq, PartialEq)]
-enum ApiReplyType {
+pub enum ApiReplyType {
     Done,
     Data,
     Trap,
     Fatal,
+    /// `print` found nothing, sent by RouterOS 7.18 and later before `!done`
+    Empty,
 }
 
+/// Single word of a sentence of the api protocol
 #[derive(Debug, Clone, Eq, PartialEq)]
-enum Query {
-    HasValue(String),
-    HasNoValue(String),
-    Equals { key: String, value: String },
-    Lt { key: String, value: String },
-    Gt { key: String, value: String },
-}
-
-#[derive(Debug, Clone, Eq, PartialEq)]
-enum ApiWord {
+pub enum ApiWord {
     Command(String),
     Attribute { key: String, value: String },
     ApiAttribute { key: String, value: String },
@@ -39,82 +60,66 @@ enum ApiWord {
 }
 
 impl ApiWord {
-    pub fn parse(word: &Vec<u8>) -> Option<ApiWord> {
-        if word.is_empty() {
-            None
-        } else {
-            match word[0] as char {
-                '/' => Some(ApiWord::Command(String::from_utf8_lossy(&word[1..]).into())),
-                '=' => {
-                    let (key, value) = Self::split_attributes(&word, 1);
-
-                    Some(ApiWord::Attribute {
-                        key,
-                        value: value.unwrap_or_else(|| String::from("")),
-                    })
-                }
-                '.' => {
-                    let (key, value) = Self::split_attributes(&word, 1);
-                    Some(ApiWord::ApiAttribute {
-                        key,
-                        value: value.unwrap_or_else(|| String::from("")),
-                    })
+    /// Decodes a word, `None` for the empty word that ends a sentence.
+    ///
+    /// Values may contain `=`, only the first one separates the key. Values that are not
+    /// valid UTF-8, e.g. comments in the legacy codepage of the router, are read as
+    /// Latin-1, commands, reply types and keys that are not valid UTF-8 are a protocol error.
+    pub fn parse(word: &[u8]) -> Result<Option<ApiWord>, RosError> {
+        let (first, rest) = match word.split_first() {
+            Some(split) => split,
+            None => return Ok(None),
+        };
+        let parsed = match first {
+            b'/' => ApiWord::Command(Self::text(rest, word)?),
+            b'=' => {
+                let (key, value) = Self::split_attributes(rest, word)?;
+                ApiWord::Attribute {
+                    key,
+                    value: value.unwrap_or_default(),
                 }
-                '!' => {
-                    let cow = String::from_utf8_lossy(&word[1..]);
-                    let x: &str = cow.trim();
-                    let t = match x {
-                        "done" => ApiReplyType::Done,
-                        "re" => ApiReplyType::Data,
-                        "trap" => ApiReplyType::Trap,
-                        _ => ApiReplyType::Fatal,
-                    };
-                    Some(ApiWord::Reply(t))
+            }
+            b'.' => {
+                let (key, value) = Self::split_attributes(rest, word)?;
+                ApiWord::ApiAttribute {
+                    key,
+                    value: value.unwrap_or_default(),
                 }
-                '?' => {
-                    if word.len() < 2 {
-                        None
-                    } else {
-                        match word[1] as char {
-                            '-' => Some(ApiWord::Query(Query::HasNoValue(
-                                String::from_utf8_lossy(&word[2..]).into(),
-                            ))),
-                            '=' => {
-                                let (key, value) = Self::split_attributes(&word, 2);
-                                Some(ApiWord::Query(Query::Equals {
-                                    key,
-                                    value: value.unwrap_or_else(|| String::from("")),
-                                }))
-                            }
-                            '>' => {
-                                let (key, value) = Self::split_attributes(&word, 2);
-                                Some(ApiWord::Query(Query::Lt {
-                                    key,
-                                    value: value.unwrap_or_else(|| String::from("")),
-                                }))
-                            }
-                            '<' => {
-                                let (key, value) = Self::split_attributes(&word, 2);
-                                Some(ApiWord::Query(Query::Gt {
-                                    key,
-                                    value: value.unwrap_or_else(|| String::from("")),
-                                }))
-                            }
-                            _ => {
-                                let (key, value) = Self::split_attributes(&word, 1);
-                                match value {
-                                    Some(value) => {
-                                        Some(ApiWord::Query(Query::Equals { key, value }))
-                                    }
-                                    None => Some(ApiWord::Query(Query::HasValue(key))),
-                                }
-                            }
+            }
+            b'!' => ApiWord::Reply(match Self::text(rest, word)?.trim() {
+                "done" => ApiReplyType::Done,
+                "re" => ApiReplyType::Data,
+                "trap" => ApiReplyType::Trap,
+                "fatal" => ApiReplyType::Fatal,
+                "empty" => ApiReplyType::Empty,
+                _ => return Err(RosError::protocol_error("Unknown reply type", word)),
+            }),
+            b'?' => {
+                let (operator, operand) = match rest.split_first() {
+                    Some(split) => split,
+                    None => return Err(RosError::protocol_error("Empty query", word)),
+                };
+                ApiWord::Query(match operator {
+                    b'#' => Query::Operations(Self::text(operand, word)?),
+                    b'-' => Query::HasNoValue(Self::text(operand, word)?),
+                    b'=' | b'<' | b'>' => {
+                        let (key, value) = Self::split_attributes(operand, word)?;
+                        let value = value.unwrap_or_default();
+                        match operator {
+                            b'=' => Query::Equals { key, value },
+                            b'<' => Query::Lt { key, value },
+                            _ => Query::Gt { key, value },
                         }
                     }
-                }
-                _ => panic!("Unsupported word: {}", String::from_utf8_lossy(word)),
+                    _ => match Self::split_attributes(rest, word)? {
+                        (key, Some(value)) => Query::Equals { key, value },
+                        (key, None) => Query::HasValue(key),
+                    },
+                })
             }
-        }
+            _ => return Err(RosError::protocol_error("Unsupported word", word)),
+        };
+        Ok(Some(parsed))
     }
 
     pub fn command<S>(cmd: S) -> ApiWord
@@ -161,192 +166,121 @@ impl ApiWord {
                 Query::Equals { key, value } => format!("?={}={}", key, value).into_bytes(),
                 Query::Lt { key, value } => format!("?<{}={}", key, value).into_bytes(),
                 Query::Gt { key, value } => format!("?>{}={}", key, value).into_bytes(),
+                Query::Operations(operations) => format!("?#{}", operations).into_bytes(),
             },
             ApiWord::Reply(code) => match code {
                 ApiReplyType::Done => "!done",
                 ApiReplyType::Data => "!re",
                 ApiReplyType::Trap => "!trap",
                 ApiReplyType::Fatal => "!fatal",
+                ApiReplyType::Empty => "!empty",
             }
             .as_bytes()
             .into(),
         }
     }
 
-    fn split_attributes(word: &&Vec<u8>, offset: usize) -> (String, Option<String>) {
-        match word[offset..].iter().position(|c| *c == ('=' as u8)) {
-            Some(n) => (
-                String::from_utf8_lossy(&word[offset..n + 1]).into(),
-                Some(String::from_utf8_lossy(&word[(n + 2)..]).into()),
-            ),
+    /// Part of `word` as text, the whole word is reported if it is not valid UTF-8
+    fn text(bytes: &[u8], word: &[u8]) -> Result<String, RosError> {
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| RosError::protocol_error("Word is not valid UTF-8", word))
+    }
 
-            None => (String::from_utf8_lossy(&word[offset..]).into(), None),
+    /// Value as text, mapped byte for byte as Latin-1 if it is not valid UTF-8
+    fn value(bytes: &[u8]) -> String {
+        match std::str::from_utf8(bytes) {
+            Ok(value) => value.to_owned(),
+            Err(_) => bytes.iter().map(|byte| char::from(*byte)).collect(),
         }
     }
-}
 
-pub struct ApiRos {
-    stream: TcpStream,
+    fn split_attributes(bytes: &[u8], word: &[u8]) -> Result<(String, Option<String>), RosError> {
+        Ok(match bytes.iter().position(|c| *c == b'=') {
+            Some(n) => (
+                Self::text(&bytes[..n], word)?,
+                Some(Self::value(&bytes[(n + 1)..])),
+            ),
+            None => (Self::text(bytes, word)?, None),
+        })
+    }
 }
 
-impl<'a> ApiRos {
-    pub fn new(s: TcpStream) -> ApiRos {
-        ApiRos { stream: s }
-    }
+/// Default port of the plain `api` service
+pub const API_PORT: u16 = 8728;
 
-    async fn write_bytes(&mut self, str_buff: &[u8]) -> Result<(), RosError> {
-        self.stream.write(str_buff).await?;
-        Ok(())
-    }
+/// Byte stream the api protocol is spoken over
+pub trait ApiStream: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
 
-    async fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, RosError> {
-        let mut buff: Vec<u8> = Vec::with_capacity(length);
-        let result = self.stream.read_buf(&mut buff).await;
-        match result {
-            Ok(result) => {
-                if result < length {
-                    let mut pointer = result;
-                    while pointer < length {
-                        let mut remaining_buff: Vec<u8> = Vec::with_capacity(length - pointer);
-                        let result = self.stream.read_buf(&mut remaining_buff).await;
-                        match result {
-                            Ok(result) => {
-                                buff.append(&mut remaining_buff);
-                                pointer += result;
-                            }
-                            Err(e) => return Err(RosError::TokioError(e)),
-                        }
-                    }
-                    Ok(buff)
-                } else if result == length {
-                    Ok(buff)
-                } else {
-                    Err(RosError::from(format!(
-                        "Expected {}, but received {} bytes",
-                        length, result
-                    )))
-                }
-            }
-            Err(e) => Err(RosError::TokioError(e)),
-        }
-    }
-
-    async fn write_len(&mut self, len: u32) -> Result<(), RosError> {
-        if len < 0x80 {
-            self.write_bytes(&[len as u8]).await?;
-        } else if len < 0x4000 {
-            let l = len | 0x8000;
-            self.write_bytes(&[((l >> 8) & 0xFF) as u8, (l & 0xFF) as u8])
-                .await?;
-        } else if len < 0x200000 {
-            let l = len | 0xC00000;
-            self.write_bytes(&[
-                ((l >> 16) & 0xFF) as u8,
-                ((l >> 8) & 0xFF) as u8,
-                (l & 0xFF) as u8,
-            ])
-            .await?;
-        } else if len < 0x10000000 {
-            let l = len | 0xE0000000;
-            self.write_bytes(&[
-                ((l >> 16) & 0xFF) as u8,
-                ((l >> 24) & 0xFF) as u8,
-                ((l >> 8) & 0xFF) as u8,
-                (l & 0xFF) as u8,
-            ])
-            .await?;
-        } else {
-            self.write_bytes(&[
-                (0xF0) as u8,
-                ((len >> 24) & 0xFF) as u8,
-                ((len >> 16) & 0xFF) as u8,
-                ((len >> 8) & 0xFF) as u8,
-                (len & 0xFF) as u8,
-            ])
-            .await?;
-        }
-        Ok(())
-    }
+impl<S> ApiStream for S where S: AsyncRead + AsyncWrite + Unpin + Send + Sync {}
 
-    async fn read_len(&mut self) -> Result<u32, RosError> {
-        let c: u32 = self.read_bytes(1).await?[0] as u32;
-        if c & 0x80 == 0x00 {
-            return Ok(c);
-        } else if c & 0xC0 == 0x80 {
-            let bytes = self.read_bytes(1).await?;
-            return Ok(((c & !0xC0) << 8) + bytes[0] as u32);
-        } else if c & 0xE0 == 0xC0 {
-            let bytes = self.read_bytes(2).await?;
-            return Ok(((c & !0xE0) << 16) + ((bytes[0] as u32) << 8) + bytes[1] as u32);
-        } else if c & 0xF0 == 0xE0 {
-            let bytes = self.read_bytes(3).await?;
-            return Ok(((c & !0xF0) << 24)
-                + ((bytes[0] as u32) << 16)
-                + ((bytes[1] as u32) << 8)
-                + bytes[2] as u32);
-        } else if c & 0xF8 == 0xF0 {
-            let bytes = self.read_bytes(4).await?;
-            return Ok(((c & !0xF0) << 32)
-                + ((bytes[0] as u32) << 24)
-                + ((bytes[1] as u32) << 16)
-                + ((bytes[2] as u32) << 8)
-                + bytes[3] as u32);
-        }
-        Err(RosError::SimpleMessage(format!(
-            "Unsupported length pattern: {:x}",
-            c
-        )))
-    }
-
-    async fn read_word(&mut self) -> Result<Option<ApiWord>, RosError> {
-        let token = self.read_token().await?;
-        let parsed = ApiWord::parse(&token);
-        if cfg!(feature = "debug") {
-            println!(">>> {:?}", parsed);
-        }
-        Ok(parsed)
-    }
+/// Transport used by [`ApiClient`] to reach the router
+#[derive(Debug, Clone)]
+pub enum Transport {
+    /// Unencrypted `api` service
+    Plain,
+    /// `api-ssl` service
+    Tls(TlsConfig),
+}
 
-    async fn read_token(&mut self) -> Result<Vec<u8>, RosError> {
-        let len = self.read_len().await?;
-        if len == 0 {
-            Ok(vec![])
-        } else {
-            self.read_bytes(len as usize).await
+impl Transport {
+    pub fn default_port(&self) -> u16 {
+        match self {
+            Transport::Plain => API_PORT,
+            Transport::Tls(_) => API_SSL_PORT,
         }
     }
+    /// Opens the byte stream to the router, e.g. to wrap it in a
+    /// [`record::RecordingStream`] before [`ApiClient::from_stream`]
+    pub async fn open(&self, target: SocketAddr) -> Result<Box<dyn ApiStream>, RosError> {
+        let stream: TcpStream = TcpStream::connect(target).await?;
+        Ok(match self {
+            Transport::Plain => Box::new(stream),
+            Transport::Tls(config) => Box::new(config.connect(target.ip(), stream).await?),
+        })
+    }
+}
 
-    async fn write_word(&mut self, w: &ApiWord) -> Result<(), RosError> {
-        if cfg!(feature = "debug") {
-            println!("<<< {:?}", w);
-        }
-        let token = w.encode();
-        self.write_token(&token).await?;
-        Ok(())
+/// Api session on a byte stream, framed by [`SentenceCodec`]
+pub struct ApiRos<S = Box<dyn ApiStream>> {
+    framed: Framed<S, SentenceCodec>,
+}
+
+impl ApiRos {
+    pub fn new<S: ApiStream + 'static>(s: S) -> ApiRos {
+        Self::with_stream(Box::new(s) as Box<dyn ApiStream>)
     }
+}
 
-    async fn write_token(&mut self, token: &Vec<u8>) -> Result<(), RosError> {
-        self.write_len(token.len() as u32).await?;
-        self.write_bytes(&token).await?;
-        Ok(())
+impl<S: AsyncRead + AsyncWrite + Unpin> ApiRos<S> {
+    /// Speaks the protocol over any byte stream, e.g. an in-memory pipe
+    pub fn with_stream(stream: S) -> ApiRos<S> {
+        ApiRos {
+            framed: Framed::new(stream, SentenceCodec::new()),
+        }
     }
 
-    async fn write_sentence<I>(&mut self, words: I) -> Result<u32, RosError>
+    async fn write_sentence<I>(&mut self, words: I) -> Result<(), RosError>
     where
         I: Iterator<Item = ApiWord>,
     {
-        let mut ret: u32 = 0;
-        for w in words {
-            self.write_word(&w).await?;
-            ret += 1;
-        }
-        self.write_len(0).await?;
-        if cfg!(feature = "debug") {
-            println!("====================");
+        self.framed.send(words.collect()).await
+    }
+
+    async fn read_sentence(&mut self) -> Result<Vec<ApiWord>, RosError> {
+        match self.framed.next().await {
+            Some(Ok(Ok(sentence))) => Ok(sentence),
+            Some(Ok(Err(malformed))) => Err(malformed.error),
+            Some(Err(error)) => Err(error),
+            None => Err(Closed::Io(
+                io::ErrorKind::UnexpectedEof,
+                String::from("Connection closed"),
+            )
+            .error()),
         }
-        Ok(ret)
     }
+}
 
+impl<S: AsyncRead + AsyncWrite + Unpin> ApiRos<S> {
     async fn talk<W, C>(&mut self, words: W, callback: &mut C) -> Result<(), RosError>
     where
         W: IntoIterator<Item = ApiWord>,
@@ -357,18 +291,18 @@ impl<'a> ApiRos {
         let mut errors: Vec<RosError> = Vec::new();
 
         loop {
-            let raw_token = self.read_word().await?;
-            if let Some(token) = raw_token {
-                let is_done = token == ApiWord::Reply(ApiReplyType::Done);
-                Self::push_err(&mut errors, callback(token));
-                if is_done {
-                    return if errors.is_empty() {
-                        Ok(())
-                    } else {
-                        Err(RosError::Umbrella(errors))
-                    };
-                }
-            };
+            let sentence = self.read_sentence().await?;
+            let is_done = sentence.first() == Some(&ApiWord::Reply(ApiReplyType::Done));
+            for word in sentence {
+                Self::push_err(&mut errors, callback(word));
+            }
+            if is_done {
+                return if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(RosError::Umbrella(errors))
+                };
+            }
         }
     }
 
@@ -392,50 +326,419 @@ impl<'a> ApiRos {
         Ok(r)
     }
 
-    pub async fn login(&mut self, username: String, pwd: String) -> Result<bool, RosError> {
+    /// Logs in with the plaintext method introduced in RouterOS 6.43 and falls back to the
+    /// MD5 challenge-response exchange if the router answers with a challenge.
+    pub async fn login(&mut self, username: String, pwd: String) -> Result<LoginScheme, RosError> {
         let login_response = self
             .talk_vec([
                 ApiWord::command("login"),
-                ApiWord::attribute("name", username),
-                ApiWord::attribute("password", pwd),
+                ApiWord::attribute("name", &username),
+                ApiWord::attribute("password", &pwd),
             ])
             .await?;
         if cfg!(feature = "debug") {
             println!("Login response: {:?}", login_response);
         }
+        let challenge = match Self::check_login_response(&login_response, LoginScheme::Plain)? {
+            Some(challenge) => challenge,
+            None => return Ok(LoginScheme::Plain),
+        };
+        let challenge = decode_hex(&challenge).ok_or_else(|| RosError::AuthenticationError {
+            scheme: LoginScheme::Challenge,
+            message: format!("Invalid login challenge: {challenge}"),
+        })?;
+
+        let login_response = self
+            .talk_vec([
+                ApiWord::command("login"),
+                ApiWord::attribute("name", username),
+                ApiWord::attribute("response", challenge_response(&pwd, &challenge)),
+            ])
+            .await?;
+        match Self::check_login_response(&login_response, LoginScheme::Challenge)? {
+            None => Ok(LoginScheme::Challenge),
+            Some(_) => Err(RosError::AuthenticationError {
+                scheme: LoginScheme::Challenge,
+                message: String::from("Router sent another challenge"),
+            }),
+        }
+    }
 
-        return if let Some(ApiWord::Reply(ApiReplyType::Done)) = login_response.get(0) {
-            Ok(true)
+    /// Returns the challenge sent by the router, if any
+    fn check_login_response(
+        response: &[ApiWord],
+        scheme: LoginScheme,
+    ) -> Result<Option<String>, RosError> {
+        let mut is_error = false;
+        let mut message = None;
+        let mut challenge = None;
+        for word in response {
+            match word {
+                ApiWord::Reply(ApiReplyType::Trap) | ApiWord::Reply(ApiReplyType::Fatal) => {
+                    is_error = true
+                }
+                ApiWord::Attribute { key, value } if key == "message" => {
+                    message = Some(value.clone())
+                }
+                ApiWord::Attribute { key, value } if key == "ret" => {
+                    challenge = Some(value.clone())
+                }
+                _ => {}
+            }
+        }
+        if is_error {
+            Err(RosError::AuthenticationError {
+                scheme,
+                message: message.unwrap_or_else(|| String::from("Login failed")),
+            })
         } else {
-            Ok(false)
+            Ok(challenge)
+        }
+    }
+}
+
+fn challenge_response(password: &str, challenge: &[u8]) -> String {
+    let mut md = Md5::new();
+    md.update([0]);
+    md.update(password.as_bytes());
+    md.update(challenge);
+    let digest: String = md.finalize().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("00{digest}")
+}
+
+fn decode_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(value.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+/// Authentication method accepted by the router
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LoginScheme {
+    /// Name and password in the login command, RouterOS 6.43 and later
+    Plain,
+    /// MD5 challenge-response used before RouterOS 6.43
+    Challenge,
+}
+
+type Sentence = Vec<ApiWord>;
+
+/// Category of a `!trap` reply
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TrapCategory {
+    /// Missing item or command
+    MissingItemOrCommand,
+    /// Argument value failure
+    ArgumentValueFailure,
+    /// Execution of the command was interrupted, e.g. by `/cancel`
+    ExecutionInterrupted,
+    /// Scripting related failure
+    ScriptingFailure,
+    /// General failure
+    GeneralFailure,
+    /// Api related failure
+    ApiFailure,
+    /// Tty related failure
+    TtyFailure,
+    /// Value generated with the `:return` command
+    ReturnValue,
+    /// Category not known to this crate
+    Other(u32),
+}
+
+impl TrapCategory {
+    fn from_api(value: &str) -> Option<TrapCategory> {
+        Some(match value.parse::<u32>().ok()? {
+            0 => TrapCategory::MissingItemOrCommand,
+            1 => TrapCategory::ArgumentValueFailure,
+            2 => TrapCategory::ExecutionInterrupted,
+            3 => TrapCategory::ScriptingFailure,
+            4 => TrapCategory::GeneralFailure,
+            5 => TrapCategory::ApiFailure,
+            6 => TrapCategory::TtyFailure,
+            7 => TrapCategory::ReturnValue,
+            other => TrapCategory::Other(other),
+        })
+    }
+}
+
+/// Error of a `!trap` or `!fatal` reply to `command`, `None` for other sentences
+fn reply_error(command: &str, sentence: &[ApiWord]) -> Option<RosError> {
+    let reply_type = match sentence.first() {
+        Some(ApiWord::Reply(reply_type @ (ApiReplyType::Trap | ApiReplyType::Fatal))) => reply_type,
+        _ => return None,
+    };
+    let mut attributes = HashMap::new();
+    let mut tag = None;
+    for word in &sentence[1..] {
+        match word {
+            ApiWord::Attribute { key, value } => {
+                attributes.insert(key.clone(), value.clone());
+            }
+            ApiWord::ApiAttribute { key, value } if key == "tag" => tag = Some(value.clone()),
+            _ => {}
+        }
+    }
+    let message = attributes.get("message").cloned().unwrap_or_default();
+    Some(match reply_type {
+        ApiReplyType::Fatal => RosError::Fatal { message },
+        _ => RosError::Trap {
+            category: attributes
+                .get("category")
+                .and_then(|category| TrapCategory::from_api(category)),
+            message,
+            command: command.to_owned(),
+            tag,
+            attributes: Box::new(attributes),
+        },
+    })
+}
+
+/// `.id` of the entry created by an `add` command, sent as `ret` of `!done`
+fn created_id(sentences: &[Sentence]) -> Option<String> {
+    sentences
+        .iter()
+        .filter(|sentence| sentence.first() == Some(&ApiWord::Reply(ApiReplyType::Done)))
+        .flat_map(|sentence| sentence.iter())
+        .find_map(|word| match word {
+            ApiWord::Attribute { key, value } if key == "ret" => Some(value.clone()),
+            _ => None,
+        })
+}
+
+/// First error in the replies to `command`
+fn check_replies(command: &str, sentences: &[Sentence]) -> Result<(), RosError> {
+    match sentences
+        .iter()
+        .find_map(|sentence| reply_error(command, sentence))
+    {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Why a connection stopped delivering replies
+#[derive(Debug, Clone)]
+enum Closed {
+    Io(io::ErrorKind, String),
+    Fatal(String),
+}
+
+impl Closed {
+    fn error(&self) -> RosError {
+        match self {
+            Closed::Io(kind, message) => {
+                RosError::TokioError(io::Error::new(*kind, message.clone()))
+            }
+            Closed::Fatal(message) => RosError::Fatal {
+                message: message.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+struct PendingRequests {
+    senders: HashMap<String, UnboundedSender<Result<Sentence, RosError>>>,
+    closed: Option<Closed>,
+}
+
+type FramedStream = Framed<Box<dyn ApiStream>, SentenceCodec>;
+
+/// Sentence to write and the channel that learns whether it was written
+type WriteRequest = (Sentence, oneshot::Sender<Result<(), RosError>>);
+
+/// Logged in connection whose replies are dispatched to the waiting requests by their `.tag`.
+///
+/// Sentences are written by a separate task, so a request that is cancelled while its
+/// sentence is written never leaves part of it on the connection.
+struct ApiConnection {
+    writer: UnboundedSender<WriteRequest>,
+    pending: Arc<Mutex<PendingRequests>>,
+    tag_counter: AtomicU32,
+    reader: JoinHandle<()>,
+}
+
+impl Drop for ApiConnection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+impl ApiConnection {
+    fn start(api: ApiRos) -> ApiConnection {
+        let (writer, reader) = api.framed.split();
+        let pending = Arc::new(Mutex::new(PendingRequests::default()));
+        let reader = tokio::spawn(Self::read_replies(reader, pending.clone()));
+        let (sender, receiver) = unbounded_channel();
+        // ends once the connection is dropped and the queued sentences are written
+        tokio::spawn(Self::write_requests(writer, receiver));
+        ApiConnection {
+            writer: sender,
+            pending,
+            tag_counter: AtomicU32::default(),
+            reader,
+        }
+    }
+
+    async fn write_requests(
+        mut writer: SplitSink<FramedStream, Sentence>,
+        mut requests: UnboundedReceiver<WriteRequest>,
+    ) {
+        while let Some((sentence, written)) = requests.recv().await {
+            let _ = written.send(writer.send(sentence).await);
+        }
+    }
+
+    async fn read_replies(
+        mut reader: SplitStream<FramedStream>,
+        pending: Arc<Mutex<PendingRequests>>,
+    ) {
+        let error = loop {
+            let sentence = match reader.next().await {
+                Some(Ok(Ok(sentence))) => sentence,
+                // only the request the sentence belongs to learns about it, the
+                // connection stays usable
+                Some(Ok(Err(MalformedSentence { tag, error }))) => {
+                    if let Some(tag) = tag {
+                        let mut pending = pending.lock().unwrap();
+                        let delivered = pending
+                            .senders
+                            .get(&tag)
+                            .map(|sender| sender.send(Err(error)).is_ok())
+                            .unwrap_or(false);
+                        if !delivered {
+                            pending.senders.remove(&tag);
+                        }
+                    }
+                    continue;
+                }
+                Some(Err(error)) => break error,
+                None => {
+                    break Closed::Io(
+                        io::ErrorKind::UnexpectedEof,
+                        String::from("Connection closed"),
+                    )
+                    .error()
+                }
+            };
+            let tag = sentence.iter().find_map(|word| match word {
+                ApiWord::ApiAttribute { key, value } if key == "tag" => Some(value.clone()),
+                _ => None,
+            });
+            let reply_type = match sentence.first() {
+                Some(ApiWord::Reply(reply_type)) => reply_type.clone(),
+                _ => continue,
+            };
+            match tag {
+                Some(tag) => {
+                    let mut pending = pending.lock().unwrap();
+                    let is_done = reply_type == ApiReplyType::Done;
+                    let delivered = pending
+                        .senders
+                        .get(&tag)
+                        .map(|sender| sender.send(Ok(sentence)).is_ok())
+                        .unwrap_or(false);
+                    if is_done || !delivered {
+                        pending.senders.remove(&tag);
+                    }
+                }
+                None if reply_type == ApiReplyType::Fatal => {
+                    let message = sentence
+                        .iter()
+                        .find_map(|word| match word {
+                            ApiWord::Attribute { key, value } if key == "message" => {
+                                Some(value.clone())
+                            }
+                            _ => None,
+                        })
+                        .unwrap_or_default();
+                    break RosError::Fatal { message };
+                }
+                None => {}
+            }
+        };
+        let closed = match error {
+            RosError::TokioError(e) => Closed::Io(e.kind(), e.to_string()),
+            RosError::Fatal { message } => Closed::Fatal(message),
+            e => Closed::Io(io::ErrorKind::Other, e.to_string()),
         };
-        /*
-               let mut chal: Vec<u8> = Vec::new();
-               if let Some(vec) = self.talk(vec![r"/login".to_string()]).await {
-                   for (_ /*reply*/, attrs) in vec {
-                       chal = hex_binascii(attrs["=ret"].clone().as_str()).unwrap();
-                   }
-               }
-
-               let mut md = Md5::new();
-               md.input(&[0]);
-               md.input(pwd.as_bytes());
-               md.input(&chal[..]);
-
-               self.talk(vec![
-                   r"/login".to_string(),
-                   format!("=name={}", username),
-                   format!("=response=00{}", md.result_str()),
-               ])
-               .await;
-
-        */
+        let mut pending = pending.lock().unwrap();
+        for (_, sender) in pending.senders.drain() {
+            let _ = sender.send(Err(closed.error()));
+        }
+        pending.closed = Some(closed);
+    }
+
+    /// Sends a command with a fresh `.tag` and returns the channel its replies are delivered to
+    async fn request<W>(
+        &self,
+        words: W,
+    ) -> Result<(String, UnboundedReceiver<Result<Sentence, RosError>>), RosError>
+    where
+        W: IntoIterator<Item = ApiWord>,
+    {
+        let tag = self.tag_counter.fetch_add(1, Ordering::SeqCst).to_string();
+        let (sender, receiver) = unbounded_channel();
+        {
+            let mut pending = self.pending.lock().unwrap();
+            if let Some(closed) = &pending.closed {
+                return Err(closed.error());
+            }
+            pending.senders.insert(tag.clone(), sender);
+        }
+        let sentence = words.into_iter().chain(once(ApiWord::tag(&tag))).collect();
+        let (written, result) = oneshot::channel();
+        let written = match self.writer.send((sentence, written)) {
+            Ok(()) => result.await.unwrap_or_else(|_| Err(Self::writer_stopped())),
+            Err(_) => Err(Self::writer_stopped()),
+        };
+        if let Err(error) = written {
+            self.pending.lock().unwrap().senders.remove(&tag);
+            return Err(error);
+        }
+        Ok((tag, receiver))
+    }
+
+    fn writer_stopped() -> RosError {
+        Closed::Io(io::ErrorKind::BrokenPipe, String::from("Connection closed")).error()
+    }
+
+    /// Sends a command and collects all reply sentences up to `!done`
+    async fn talk<W>(&self, words: W) -> Result<Vec<Sentence>, RosError>
+    where
+        W: IntoIterator<Item = ApiWord>,
+    {
+        let (_, mut receiver) = self.request(words).await?;
+        let mut ret = Vec::new();
+        while let Some(sentence) = receiver.recv().await {
+            let sentence = sentence?;
+            let is_done = sentence.first() == Some(&ApiWord::Reply(ApiReplyType::Done));
+            ret.push(sentence);
+            if is_done {
+                return Ok(ret);
+            }
+        }
+        Err(Closed::Io(
+            io::ErrorKind::UnexpectedEof,
+            String::from("Connection closed"),
+        )
+        .error())
     }
 }
 
+/// Handle to a logged in api session, clones share the connection and may send requests
+/// concurrently.
+#[derive(Clone)]
 pub struct ApiClient {
-    api: ApiRos,
-    tag_counter: AtomicU32,
+    connection: Arc<ApiConnection>,
+    login_scheme: LoginScheme,
+    versions: VersionFilter,
+    parser: AttributeParser,
 }
 
 impl ApiClient {
@@ -444,21 +747,121 @@ impl ApiClient {
         username: String,
         password: String,
     ) -> Result<ApiClient, RosError> {
-        let stream: TcpStream = TcpStream::connect(SocketAddr::new(target, 8728))
-            .await
-            .map_err(|e| RosError::TokioError(e))?;
-        let mut api: ApiRos = ApiRos::new(stream);
-        let login_ok = api.login(username, password).await?;
-        if login_ok {
-            Ok(ApiClient {
-                api,
-                tag_counter: AtomicU32::default(),
-            })
-        } else {
-            Err(RosError::SimpleMessage(String::from("Login failed")))
+        Self::connect(
+            SocketAddr::new(target, API_PORT),
+            username,
+            password,
+            Transport::Plain,
+        )
+        .await
+    }
+
+    /// Connects to the `api-ssl` service on its default port
+    pub async fn new_tls(
+        target: IpAddr,
+        username: String,
+        password: String,
+        tls: TlsConfig,
+    ) -> Result<ApiClient, RosError> {
+        Self::connect(
+            SocketAddr::new(target, API_SSL_PORT),
+            username,
+            password,
+            Transport::Tls(tls),
+        )
+        .await
+    }
+
+    /// Logs in and detects the RouterOS version, if the user may not read it the version
+    /// stays unknown and all fields are sent
+    pub async fn connect(
+        target: SocketAddr,
+        username: String,
+        password: String,
+        transport: Transport,
+    ) -> Result<ApiClient, RosError> {
+        let mut client =
+            Self::from_stream(transport.open(target).await?, username, password).await?;
+        // users without read access to `system/resource` can still work with the router,
+        // only the version filter stays off
+        match client.detect_version().await {
+            Err(error) if error.is_connection_error() => Err(error),
+            _ => Ok(client),
         }
     }
 
+    /// Logs in over an already opened stream, e.g. a [`record::ReplayStream`], the version
+    /// of the router stays unknown until [`ApiClient::detect_version`]
+    pub async fn from_stream<S: ApiStream + 'static>(
+        stream: S,
+        username: String,
+        password: String,
+    ) -> Result<ApiClient, RosError> {
+        let mut api = ApiRos::new(stream);
+        let login_scheme = api.login(username, password).await?;
+        Ok(ApiClient {
+            connection: Arc::new(ApiConnection::start(api)),
+            login_scheme,
+            versions: VersionFilter::default(),
+            parser: AttributeParser::default(),
+        })
+    }
+
+    /// Reads the RouterOS version of the router, fields it does not support are not sent
+    /// afterwards, see [`crate::client::version`]
+    pub async fn detect_version(&mut self) -> Result<Option<RosVersion>, RosError> {
+        self.versions.version = router_version(self).await?;
+        Ok(self.versions.version)
+    }
+
+    /// RouterOS version of the router, if it was detected
+    pub fn version(&self) -> Option<RosVersion> {
+        self.versions.version
+    }
+
+    /// Sets what happens to modified fields the router version does not support
+    pub fn unsupported_fields(mut self, unsupported: UnsupportedFields) -> ApiClient {
+        self.versions.unsupported = unsupported;
+        self
+    }
+
+    /// Sets how attributes that do not fit the resources are treated, see
+    /// [`crate::model::extra`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> ApiClient {
+        self.parser.mode = mode;
+        self
+    }
+
+    /// Uses the parse mode and warnings of `parser`
+    pub(crate) fn with_parser(mut self, parser: AttributeParser) -> ApiClient {
+        self.parser = parser;
+        self
+    }
+
+    /// Whether the connection was closed, every request on this client fails afterwards
+    pub fn is_closed(&self) -> bool {
+        self.connection.pending.lock().unwrap().closed.is_some()
+    }
+
+    /// Whether both clients share the same connection
+    pub(crate) fn same_connection(&self, other: &ApiClient) -> bool {
+        Arc::ptr_eq(&self.connection, &other.connection)
+    }
+
+    /// Sends a cheap command to keep the session alive and check that the router answers
+    pub async fn keepalive(&self) -> Result<(), RosError> {
+        let command = "system/identity/print";
+        check_replies(
+            command,
+            &self.connection.talk([ApiWord::command(command)]).await?,
+        )
+    }
+
+    /// Login method that was accepted by the router
+    pub fn login_scheme(&self) -> LoginScheme {
+        self.login_scheme
+    }
+
     async fn set<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
     where
         Resource: RouterOsResource,
@@ -467,9 +870,6 @@ impl ApiClient {
         let path = Resource::resource_path();
 
         request.push(ApiWord::command(format!("{}/set", path)));
-        request.push(ApiWord::tag(
-            self.tag_counter.fetch_add(1, Ordering::SeqCst),
-        ));
         if let Some((description, value)) = resource.id_field() {
             request.push(ApiWord::attribute(
                 description.name,
@@ -478,49 +878,100 @@ impl ApiClient {
                     .unwrap_or_else(String::new),
             ));
         }
-        resource
-            .fields()
-            .filter_map(|f| f.1.modified_value(&ValueFormat::Api).map(|v| (f.0.name, v)))
-            .for_each(|(key, value)| request.push(ApiWord::attribute(key, value)));
+        for (key, value) in self
+            .versions
+            .modified_values(&resource, &ValueFormat::Api)?
+        {
+            request.push(ApiWord::attribute(key, value));
+        }
 
-        self.api.talk_vec(request).await?;
-        Ok(())
+        check_replies(
+            &format!("{}/set", path),
+            &self.connection.talk(request).await?,
+        )
     }
 }
 
 enum AttributeCollector<Resource: RouterOsResource> {
     Ressource(Resource),
-    Error(HashMap<String, String>),
     None,
 }
 
 impl<Resource: RouterOsResource> AttributeCollector<Resource> {
-    pub fn extract(self) -> (Option<Resource>, Option<HashMap<String, String>>) {
+    pub fn extract(self) -> Option<Resource> {
         match self {
-            AttributeCollector::Ressource(r) => (Some(r), None),
-            AttributeCollector::Error(error) => (None, Some(error)),
-            AttributeCollector::None => (None, None),
+            AttributeCollector::Ressource(r) => Some(r),
+            AttributeCollector::None => None,
         }
     }
-    pub fn write_attribute(&mut self, key: String, value: String) -> Result<(), RosError> {
+    pub fn write_attribute(
+        &mut self,
+        key: String,
+        value: String,
+        parser: &AttributeParser,
+    ) -> Result<(), RosError> {
         match self {
-            AttributeCollector::Ressource(r) => {
-                if let Some(field_accessor) = r
-                    .fields_mut()
-                    .find(|e| e.0.name.eq(key.as_str()))
-                    .map(|e| e.1)
-                {
-                    field_accessor.set_from_api(value.as_str())?;
-                    Ok(())
-                } else {
-                    Err(RosError::field_missing_error(key, value))
+            AttributeCollector::Ressource(r) => parser.write(r, &key, &value),
+            AttributeCollector::None => Ok(()),
+        }
+    }
+}
+
+/// Selects the entry with the key field `name`, other keys than `.id` are accepted as
+/// `numbers`, like `name` in `set numbers=wg0`
+fn dynamic_selector(name: &str, value: String) -> ApiWord {
+    match name {
+        ".id" => ApiWord::attribute(name, value),
+        _ => ApiWord::attribute("numbers", value),
+    }
+}
+
+impl ApiClient {
+    async fn print<Resource>(
+        &self,
+        queries: &[Query],
+        projection: Option<&Projection<Resource>>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        let path = Resource::resource_path();
+        let command = format!("{}/print", path);
+        let proplist = projection.map(Projection::proplist).transpose()?;
+        let request = once(ApiWord::command(&command))
+            .chain(proplist.map(|proplist| ApiWord::attribute(".proplist", proplist)))
+            .chain(queries.iter().cloned().map(ApiWord::Query))
+            .collect::<Vec<_>>();
+
+        let sentences = self.connection.talk(request).await?;
+        check_replies(&command, &sentences)?;
+
+        let mut ret = vec![];
+        let mut errors: Vec<RosError> = Vec::new();
+
+        for sentence in sentences {
+            let mut words = sentence.into_iter();
+            let mut result_builder = match words.next() {
+                Some(ApiWord::Reply(ApiReplyType::Data)) => {
+                    AttributeCollector::Ressource(Resource::default())
+                }
+                _ => AttributeCollector::None,
+            };
+            for word in words {
+                if let ApiWord::Attribute { key, value } = word {
+                    if let Err(error) = result_builder.write_attribute(key, value, &self.parser) {
+                        errors.push(error);
+                    }
                 }
             }
-            AttributeCollector::Error(error) => {
-                error.insert(key, value);
-                Ok(())
+            if let Some(resource) = result_builder.extract() {
+                ret.push(resource);
             }
-            AttributeCollector::None => Ok(()),
+        }
+        if errors.is_empty() {
+            Ok(ret)
+        } else {
+            Err(RosError::Umbrella(errors))
         }
     }
 }
@@ -531,56 +982,28 @@ impl Client for ApiClient {
     where
         Resource: RouterOsResource,
     {
-        let path = Resource::resource_path();
-        let command = format!("{}/print", path);
-        let mut result_builder = AttributeCollector::<Resource>::None;
+        self.print(&[], None).await
+    }
 
-        let mut ret = vec![];
+    async fn list_where<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.print(filter.queries()?, None).await
+    }
 
-        self.api
-            .talk(
-                [
-                    ApiWord::command(command),
-                    ApiWord::tag(self.tag_counter.fetch_add(1, Ordering::SeqCst)),
-                ],
-                &mut |word| match word {
-                    ApiWord::Command(_) => Ok(()),
-                    ApiWord::Attribute { key, value } => result_builder.write_attribute(key, value),
-
-                    ApiWord::ApiAttribute { .. } => Ok(()),
-                    ApiWord::Reply(reply_type) => {
-                        let mut new_resource = match reply_type {
-                            ApiReplyType::Done => {
-                                AttributeCollector::Ressource(Resource::default())
-                            }
-                            ApiReplyType::Data => {
-                                AttributeCollector::Ressource(Resource::default())
-                            }
-                            ApiReplyType::Trap => AttributeCollector::Error(HashMap::new()),
-                            ApiReplyType::Fatal => AttributeCollector::Error(HashMap::new()),
-                        };
-                        mem::swap(&mut new_resource, &mut result_builder);
-                        let (data, error) = new_resource.extract();
-                        if let Some(resource) = data {
-                            ret.push(resource);
-                        }
-                        if let Some(error_data) = error {
-                            let message =
-                                error_data.get("message").map(String::as_str).unwrap_or("");
-                            if message == "no such command prefix" {
-                                Ok(())
-                            } else {
-                                Err(RosError::SimpleMessage(message.to_owned()))
-                            }
-                        } else {
-                            Ok(())
-                        }
-                    }
-                    ApiWord::Query(_) => Ok(()),
-                },
-            )
-            .await?;
-        Ok(ret)
+    async fn list_projected<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+        projection: &Projection<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.print(filter.queries()?, Some(projection)).await
     }
 
     async fn update<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
@@ -599,7 +1022,11 @@ impl Client for ApiClient {
         self.set(resource).await
     }
 
-    async fn add<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    async fn add_before<Resource>(
+        &mut self,
+        resource: &mut Resource,
+        before: Option<&str>,
+    ) -> Result<Option<String>, RosError>
     where
         Resource: RouterOsListResource,
     {
@@ -607,30 +1034,159 @@ impl Client for ApiClient {
         let path = Resource::resource_path();
 
         request.push(ApiWord::command(format!("{}/add", path)));
-        resource
-            .fields()
-            .filter_map(|f| f.1.modified_value(&ValueFormat::Api).map(|v| (f.0.name, v)))
-            .for_each(|(key, value)| request.push(ApiWord::attribute(key, value)));
+        for (key, value) in self.versions.modified_values(resource, &ValueFormat::Api)? {
+            request.push(ApiWord::attribute(key, value));
+        }
+        if let Some(before) = before {
+            request.push(ApiWord::attribute("place-before", before));
+        }
 
-        let x = self.api.talk_vec(request).await?;
-        println!("Result: {:?}", x);
+        let sentences = self.connection.talk(request).await?;
+        check_replies(&format!("{}/add", path), &sentences)?;
+        let id = created_id(&sentences);
+        if let Some(id) = &id {
+            resource.set_created_id(id)?;
+        }
+        Ok(id)
+    }
+    async fn move_before<Resource>(
+        &mut self,
+        resource: Resource,
+        before: Option<&str>,
+    ) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        if let Some((_, value)) = resource.id_field() {
+            let command = format!("{}/move", Resource::resource_path());
+            let mut request = vec![
+                ApiWord::command(&command),
+                ApiWord::attribute("numbers", value.api_value(&ValueFormat::Api)),
+            ];
+            if let Some(before) = before {
+                request.push(ApiWord::attribute("destination", before));
+            }
+            check_replies(&command, &self.connection.talk(request).await?)?;
+        }
         Ok(())
     }
     async fn delete<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
     where
         Resource: RouterOsListResource,
     {
-        let mut request: Vec<ApiWord> = Vec::new();
         if let Some((description, value)) = resource.id_field() {
-            let value = value.api_value(&ValueFormat::Api);
-            let path = Resource::resource_path();
-            request.push(ApiWord::command(format!("{}/remove", path)));
-            request.push(ApiWord::attribute(description.name, value));
-        }
-        if !request.is_empty() {
-            let x = self.api.talk_vec(request).await?;
-            println!("Result: {:?}", x);
+            let command = format!("{}/remove", Resource::resource_path());
+            let request = [
+                ApiWord::command(&command),
+                ApiWord::attribute(description.name, value.api_value(&ValueFormat::Api)),
+            ];
+            check_replies(&command, &self.connection.talk(request).await?)?;
         }
         Ok(())
     }
+
+    async fn list_dynamic(&mut self, menu: &DynamicMenu) -> Result<Vec<DynamicResource>, RosError> {
+        let command = format!("{}/print", menu.path());
+        let sentences = self.connection.talk([ApiWord::command(&command)]).await?;
+        check_replies(&command, &sentences)?;
+        let mut ret = Vec::new();
+        for sentence in sentences {
+            if sentence.first() != Some(&ApiWord::Reply(ApiReplyType::Data)) {
+                continue;
+            }
+            let mut resource = menu.create();
+            for word in sentence {
+                if let ApiWord::Attribute { key, value } = word {
+                    resource.set_from_api(&key, &value)?;
+                }
+            }
+            ret.push(resource);
+        }
+        Ok(ret)
+    }
+
+    async fn update_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        let command = format!("{}/set", resource.menu().path());
+        let mut request = vec![ApiWord::command(&command)];
+        if let Some((name, value)) = resource.id_field() {
+            let value = value.original_value(&ValueFormat::Api).unwrap_or_default();
+            request.push(dynamic_selector(name, value));
+        }
+        for (key, value) in resource.modified_values(&ValueFormat::Api) {
+            request.push(ApiWord::attribute(key, value));
+        }
+        check_replies(&command, &self.connection.talk(request).await?)
+    }
+
+    async fn add_dynamic(
+        &mut self,
+        resource: &mut DynamicResource,
+    ) -> Result<Option<String>, RosError> {
+        let command = format!("{}/add", resource.menu().path());
+        let request = once(ApiWord::command(&command))
+            .chain(
+                resource
+                    .modified_values(&ValueFormat::Api)
+                    .into_iter()
+                    .map(|(key, value)| ApiWord::attribute(key, value)),
+            )
+            .collect::<Vec<_>>();
+        let sentences = self.connection.talk(request).await?;
+        check_replies(&command, &sentences)?;
+        let id = created_id(&sentences);
+        if let Some(id) = &id {
+            resource.set_created_id(id)?;
+        }
+        Ok(id)
+    }
+
+    async fn delete_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        let path = resource.menu().path();
+        let (name, value) = match resource.id_field() {
+            Some(id_field) => id_field,
+            None => {
+                return Err(RosError::SimpleMessage(format!(
+                    "Entry of {path} has no key to remove it by"
+                )))
+            }
+        };
+        let command = format!("{path}/remove");
+        let request = [
+            ApiWord::command(&command),
+            dynamic_selector(name, value.api_value(&ValueFormat::Api)),
+        ];
+        check_replies(&command, &self.connection.talk(request).await?)
+    }
+
+    async fn execute(&mut self, command: &Command) -> Result<CommandOutput, RosError> {
+        let path = command.path();
+        let request = once(ApiWord::command(&path))
+            .chain(
+                command
+                    .arguments(&ValueFormat::Api)
+                    .map(|(key, value)| ApiWord::attribute(key, value)),
+            )
+            .collect::<Vec<_>>();
+        let sentences = self.connection.talk(request).await?;
+        check_replies(&path, &sentences)?;
+        let ret = created_id(&sentences);
+        let rows = sentences
+            .into_iter()
+            .filter(|sentence| sentence.first() == Some(&ApiWord::Reply(ApiReplyType::Data)))
+            .map(|sentence| {
+                sentence
+                    .into_iter()
+                    .filter_map(|word| match word {
+                        ApiWord::Attribute { key, value } => Some((key, value)),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(CommandOutput { rows, ret })
+    }
+
+    fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
 }
@@ -0,0 +1,205 @@
+use std::mem::take;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::client::api::{ApiReplyType, ApiWord};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Largest word accepted by default, longer ones are rejected before they are buffered
+pub const DEFAULT_MAX_WORD_LENGTH: usize = 64 * 1024 * 1024;
+
+/// Appends the length prefix of a word of `len` bytes
+pub fn encode_length(len: u32, dst: &mut BytesMut) {
+    if len < 0x80 {
+        dst.put_u8(len as u8);
+    } else if len < 0x4000 {
+        dst.put_u16(len as u16 | 0x8000);
+    } else if len < 0x20_0000 {
+        let l = len | 0xC0_0000;
+        dst.put_slice(&l.to_be_bytes()[1..]);
+    } else if len < 0x1000_0000 {
+        dst.put_u32(len | 0xE000_0000);
+    } else {
+        dst.put_u8(0xF0);
+        dst.put_u32(len);
+    }
+}
+
+/// Reads a length prefix from the start of `src`.
+///
+/// Returns the length and the number of bytes of the prefix, or `None` if `src` does not
+/// hold the complete prefix yet.
+pub fn decode_length(src: &[u8]) -> Result<Option<(u32, usize)>, RosError> {
+    let first = match src.first() {
+        Some(first) => *first,
+        None => return Ok(None),
+    };
+    let (size, mask) = match first {
+        c if c & 0x80 == 0x00 => (1, 0x7F),
+        c if c & 0xC0 == 0x80 => (2, 0x3F),
+        c if c & 0xE0 == 0xC0 => (3, 0x1F),
+        c if c & 0xF0 == 0xE0 => (4, 0x0F),
+        c if c & 0xF8 == 0xF0 => (5, 0x00),
+        _ => {
+            return Err(RosError::protocol_error(
+                "Unsupported length prefix",
+                &src[..1],
+            ))
+        }
+    };
+    if src.len() < size {
+        return Ok(None);
+    }
+    let len = src[1..size]
+        .iter()
+        .fold((first & mask) as u32, |len, byte| (len << 8) | *byte as u32);
+    Ok(Some((len, size)))
+}
+
+/// Frames single words, the empty word ends a sentence
+#[derive(Debug, Clone)]
+pub struct WordCodec {
+    max_word_length: usize,
+}
+
+impl Default for WordCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WordCodec {
+    pub fn new() -> WordCodec {
+        Self::with_max_word_length(DEFAULT_MAX_WORD_LENGTH)
+    }
+    pub fn with_max_word_length(max_word_length: usize) -> WordCodec {
+        WordCodec { max_word_length }
+    }
+}
+
+impl Decoder for WordCodec {
+    type Item = BytesMut;
+    type Error = RosError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let (len, prefix) = match decode_length(src)? {
+            Some(length) => length,
+            None => return Ok(None),
+        };
+        let len = len as usize;
+        if len > self.max_word_length {
+            return Err(RosError::protocol_error(
+                "Word exceeds the maximum length",
+                &src[..prefix],
+            ));
+        }
+        if src.len() < prefix + len {
+            src.reserve(prefix + len - src.len());
+            return Ok(None);
+        }
+        src.advance(prefix);
+        Ok(Some(src.split_to(len)))
+    }
+}
+
+impl Encoder<&[u8]> for WordCodec {
+    type Error = RosError;
+
+    fn encode(&mut self, word: &[u8], dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let len = u32::try_from(word.len())
+            .map_err(|_| RosError::protocol_error("Word exceeds the maximum length", &[]))?;
+        dst.reserve(word.len() + 5);
+        encode_length(len, dst);
+        dst.put_slice(word);
+        Ok(())
+    }
+}
+
+/// Sentence with a word that could not be decoded.
+///
+/// The whole sentence is discarded, the words after it are still read so the stream stays
+/// in sync and the error can be routed to the request the sentence belongs to.
+#[derive(Debug)]
+pub struct MalformedSentence {
+    /// `.tag` of the sentence, if it could be decoded
+    pub tag: Option<String>,
+    pub error: RosError,
+}
+
+/// Frames sentences of [`ApiWord`]s, e.g. for `tokio_util::codec::Framed`
+#[derive(Debug, Default)]
+pub struct SentenceCodec {
+    words: WordCodec,
+    sentence: Vec<ApiWord>,
+    /// First word of the current sentence that could not be decoded
+    error: Option<RosError>,
+}
+
+impl SentenceCodec {
+    pub fn new() -> SentenceCodec {
+        Self::default()
+    }
+    pub fn with_max_word_length(max_word_length: usize) -> SentenceCodec {
+        SentenceCodec {
+            words: WordCodec::with_max_word_length(max_word_length),
+            sentence: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+impl Decoder for SentenceCodec {
+    /// A malformed sentence is an item, only broken framing is an error of the stream
+    type Item = Result<Vec<ApiWord>, MalformedSentence>;
+    type Error = RosError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        while let Some(token) = self.words.decode(src)? {
+            let word = match ApiWord::parse(&token) {
+                Ok(Some(word)) => word,
+                Ok(None) => {
+                    let sentence = take(&mut self.sentence);
+                    if cfg!(feature = "debug") {
+                        println!(">>> {:?}", sentence);
+                    }
+                    if let Some(error) = self.error.take() {
+                        let tag = sentence.into_iter().find_map(|word| match word {
+                            ApiWord::ApiAttribute { key, value } if key == "tag" => Some(value),
+                            _ => None,
+                        });
+                        return Ok(Some(Err(MalformedSentence { tag, error })));
+                    }
+                    return Ok(Some(Ok(sentence)));
+                }
+                // `!fatal` is followed by the reason as plain text
+                Err(_) if self.sentence == [ApiWord::Reply(ApiReplyType::Fatal)] => {
+                    ApiWord::attribute("message", String::from_utf8_lossy(&token))
+                }
+                Err(error) => {
+                    self.error.get_or_insert(error);
+                    continue;
+                }
+            };
+            self.sentence.push(word);
+        }
+        Ok(None)
+    }
+}
+
+impl Encoder<Vec<ApiWord>> for SentenceCodec {
+    type Error = RosError;
+
+    fn encode(&mut self, sentence: Vec<ApiWord>, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if cfg!(feature = "debug") {
+            println!("<<< {:?}", sentence);
+        }
+        for word in &sentence {
+            self.words.encode(word.encode().as_slice(), dst)?;
+        }
+        self.words.encode(&[][..], dst)
+    }
+}
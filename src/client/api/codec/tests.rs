@@ -0,0 +1,145 @@
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::client::api::codec::{decode_length, encode_length, SentenceCodec, WordCodec};
+use crate::client::api::{ApiReplyType, ApiWord};
+use crate::RosError;
+
+const BOUNDARIES: [(u32, &[u8]); 10] = [
+    (0, &[0x00]),
+    (0x7F, &[0x7F]),
+    (0x80, &[0x80, 0x80]),
+    (0x3FFF, &[0xBF, 0xFF]),
+    (0x4000, &[0xC0, 0x40, 0x00]),
+    (0x1F_FFFF, &[0xDF, 0xFF, 0xFF]),
+    (0x20_0000, &[0xE0, 0x20, 0x00, 0x00]),
+    (0x0FFF_FFFF, &[0xEF, 0xFF, 0xFF, 0xFF]),
+    (0x1000_0000, &[0xF0, 0x10, 0x00, 0x00, 0x00]),
+    (u32::MAX, &[0xF0, 0xFF, 0xFF, 0xFF, 0xFF]),
+];
+
+#[test]
+fn lengths_at_class_boundaries() {
+    for (len, encoded) in BOUNDARIES {
+        let mut buffer = BytesMut::new();
+        encode_length(len, &mut buffer);
+        assert_eq!(&buffer[..], encoded, "encoding {len:#x}");
+        assert_eq!(
+            decode_length(encoded).unwrap(),
+            Some((len, encoded.len())),
+            "decoding {len:#x}"
+        );
+        for partial in 0..encoded.len() {
+            assert_eq!(decode_length(&encoded[..partial]).unwrap(), None);
+        }
+    }
+}
+
+#[test]
+fn reserved_length_prefixes_are_rejected() {
+    for prefix in 0xF8..=0xFF {
+        assert!(matches!(
+            decode_length(&[prefix, 0, 0, 0, 0]),
+            Err(RosError::ProtocolError { .. })
+        ));
+    }
+}
+
+#[test]
+fn words_wait_for_all_bytes() {
+    let word = vec![b'x'; 0x4000];
+    let mut encoded = BytesMut::new();
+    WordCodec::new().encode(&word[..], &mut encoded).unwrap();
+    assert_eq!(encoded.len(), word.len() + 3);
+
+    let mut codec = WordCodec::new();
+    let mut buffer = BytesMut::new();
+    for chunk in encoded.chunks(1000) {
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        buffer.extend_from_slice(chunk);
+    }
+    assert_eq!(&codec.decode(&mut buffer).unwrap().unwrap()[..], &word[..]);
+    assert!(buffer.is_empty());
+
+    let mut buffer = BytesMut::from(&[0x81, 0x00][..]);
+    assert!(matches!(
+        WordCodec::with_max_word_length(0xFF).decode(&mut buffer),
+        Err(RosError::ProtocolError { .. })
+    ));
+}
+
+#[test]
+fn sentences_are_framed_by_the_empty_word() {
+    let first = vec![
+        ApiWord::Reply(ApiReplyType::Data),
+        ApiWord::attribute("comment", "x".repeat(300)),
+    ];
+    let second = vec![ApiWord::Reply(ApiReplyType::Done)];
+    let mut buffer = BytesMut::new();
+    let mut codec = SentenceCodec::new();
+    codec.encode(first.clone(), &mut buffer).unwrap();
+    codec.encode(second.clone(), &mut buffer).unwrap();
+
+    let mut received = BytesMut::new();
+    let mut sentences = Vec::new();
+    for byte in buffer {
+        received.extend_from_slice(&[byte]);
+        if let Some(sentence) = codec.decode(&mut received).unwrap() {
+            sentences.push(sentence.unwrap());
+        }
+    }
+    assert_eq!(sentences, [first, second]);
+}
+
+#[test]
+fn fatal_reason_becomes_message() {
+    let mut buffer = BytesMut::new();
+    let mut words = WordCodec::new();
+    for word in [&b"!fatal"[..], b"session terminated on request", b""] {
+        words.encode(word, &mut buffer).unwrap();
+    }
+    assert_eq!(
+        SentenceCodec::new()
+            .decode(&mut buffer)
+            .unwrap()
+            .unwrap()
+            .unwrap(),
+        vec![
+            ApiWord::Reply(ApiReplyType::Fatal),
+            ApiWord::attribute("message", "session terminated on request"),
+        ]
+    );
+}
+
+#[test]
+fn malformed_sentences_are_skipped_with_their_tag() {
+    let mut buffer = BytesMut::new();
+    let mut words = WordCodec::new();
+    for word in [
+        &b"!re"[..],
+        b"=na\xffme=ether1",
+        b"!unknown",
+        b".tag=7",
+        b"",
+        b"!done",
+        b".tag=8",
+        b"",
+    ] {
+        words.encode(word, &mut buffer).unwrap();
+    }
+    let mut codec = SentenceCodec::new();
+    match codec.decode(&mut buffer).unwrap().unwrap() {
+        Err(malformed) => {
+            assert_eq!(malformed.tag.as_deref(), Some("7"));
+            assert!(matches!(
+                malformed.error,
+                RosError::ProtocolError { word, .. } if word == b"=na\xffme=ether1"
+            ));
+        }
+        Ok(sentence) => panic!("decoded {sentence:?}"),
+    }
+    assert_eq!(
+        codec.decode(&mut buffer).unwrap().unwrap().unwrap(),
+        vec![ApiWord::Reply(ApiReplyType::Done), ApiWord::tag(8)]
+    );
+}
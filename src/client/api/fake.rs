@@ -0,0 +1,391 @@
+//! In-process RouterOS api server for tests, enabled by the `fake-server` feature.
+//!
+//! The server listens on localhost and keeps the entries of every menu in memory. It knows
+//! `login`, `print` with queries and `.proplist`, `add`, `set`, `remove`, `move` and
+//! `cancel`, further replies can be injected to test error handling.
+//!
+//! ```ignore
+//! let server = FakeServer::start("admin", "secret").await?;
+//! server.insert("ip/address", &[("address", "10.0.0.1/24"), ("interface", "ether1")]);
+//! let mut client = server.client().await?;
+//! let addresses: Vec<Address> = client.list().await?;
+//! ```
+
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+
+use bytes::BytesMut;
+use futures::{SinkExt, StreamExt};
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+use tokio_util::codec::{Encoder, Framed};
+
+use crate::client::api::codec::{SentenceCodec, WordCodec};
+use crate::client::api::{ApiClient, ApiReplyType, ApiWord, Sentence, Transport};
+use crate::client::filter::{evaluate, Query};
+use crate::RosError;
+
+/// Attributes of an entry by name, including `.id`
+pub type Entry = HashMap<String, String>;
+
+/// Reply sent instead of executing a command, see [`FakeServer::inject`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum InjectedReply {
+    /// `!trap` followed by `!done`
+    Trap {
+        category: Option<u32>,
+        message: String,
+    },
+    /// `!fatal` with the reason, the server closes the connection afterwards
+    Fatal(String),
+}
+
+#[derive(Default)]
+struct State {
+    username: String,
+    password: String,
+    menus: HashMap<String, Vec<Entry>>,
+    last_id: u32,
+    injected: Vec<(String, InjectedReply)>,
+    commands: Vec<String>,
+}
+
+impl State {
+    fn insert(&mut self, path: &str, mut entry: Entry) -> String {
+        self.last_id += 1;
+        let id = format!("*{:X}", self.last_id);
+        entry.insert(String::from(".id"), id.clone());
+        self.menus.entry(path.to_owned()).or_default().push(entry);
+        id
+    }
+}
+
+/// Entry with the `.id` or, like `numbers` accepts it, the `name` `id`
+fn position(entries: &[Entry], id: &str) -> Option<usize> {
+    let by = |key: &str| {
+        entries
+            .iter()
+            .position(|entry| entry.get(key).map(String::as_str) == Some(id))
+    };
+    by(".id").or_else(|| by("name"))
+}
+
+/// Api server on localhost, stopped when dropped
+pub struct FakeServer {
+    address: SocketAddr,
+    state: Arc<Mutex<State>>,
+    server: JoinHandle<()>,
+}
+
+impl Drop for FakeServer {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+impl FakeServer {
+    /// Starts the server on a free port, it accepts the given credentials
+    pub async fn start(username: &str, password: &str) -> Result<FakeServer, RosError> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State {
+            username: username.to_owned(),
+            password: password.to_owned(),
+            ..Default::default()
+        }));
+        let server = tokio::spawn(serve(listener, state.clone()));
+        Ok(FakeServer {
+            address,
+            state,
+            server,
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Connects and logs in with the credentials the server was started with
+    pub async fn client(&self) -> Result<ApiClient, RosError> {
+        let (username, password) = {
+            let state = self.state.lock().unwrap();
+            (state.username.clone(), state.password.clone())
+        };
+        ApiClient::connect(self.address, username, password, Transport::Plain).await
+    }
+
+    /// Adds an entry to the menu at `path`, e.g. `ip/address`, and returns its `.id`
+    pub fn insert(&self, path: &str, attributes: &[(&str, &str)]) -> String {
+        let entry = attributes
+            .iter()
+            .map(|(key, value)| ((*key).to_owned(), (*value).to_owned()))
+            .collect();
+        self.state.lock().unwrap().insert(menu(path), entry)
+    }
+
+    /// Current entries of the menu at `path`
+    pub fn entries(&self, path: &str) -> Vec<Entry> {
+        self.state
+            .lock()
+            .unwrap()
+            .menus
+            .get(menu(path))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Answers the next `command`, e.g. `ip/address/add`, with `reply` instead of
+    /// executing it. Replies injected for the same command are sent in order.
+    pub fn inject(&self, command: &str, reply: InjectedReply) {
+        self.state
+            .lock()
+            .unwrap()
+            .injected
+            .push((menu(command).to_owned(), reply));
+    }
+
+    /// Commands executed so far in all sessions, e.g. `ip/address/add`, without `login`
+    pub fn commands(&self) -> Vec<String> {
+        self.state.lock().unwrap().commands.clone()
+    }
+}
+
+fn menu(path: &str) -> &str {
+    path.trim_matches('/')
+}
+
+async fn serve(listener: TcpListener, state: Arc<Mutex<State>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(session(stream, state.clone()));
+    }
+}
+
+async fn session(stream: TcpStream, state: Arc<Mutex<State>>) {
+    let mut framed = Framed::new(stream, SentenceCodec::new());
+    let mut logged_in = false;
+    while let Some(Ok(request)) = framed.next().await {
+        let request = match request {
+            Ok(request) => request,
+            Err(_) => continue,
+        };
+        let command = match request.first() {
+            Some(ApiWord::Command(command)) => command.clone(),
+            _ => continue,
+        };
+        let tag = request.iter().find_map(|word| match word {
+            ApiWord::ApiAttribute { key, value } if key == "tag" => Some(value.clone()),
+            _ => None,
+        });
+        let injected = {
+            let mut state = state.lock().unwrap();
+            let position = state.injected.iter().position(|(c, _)| *c == command);
+            position.map(|position| state.injected.remove(position).1)
+        };
+        let mut replies = match injected {
+            Some(InjectedReply::Fatal(reason)) => {
+                let _ = send_fatal(&mut framed, &reason).await;
+                return;
+            }
+            Some(InjectedReply::Trap { category, message }) => vec![trap(category, &message)],
+            None if command == "login" => {
+                let state = state.lock().unwrap();
+                let attributes = attributes(&request);
+                if attributes.get("name") == Some(&state.username)
+                    && attributes.get("password") == Some(&state.password)
+                {
+                    logged_in = true;
+                    Vec::new()
+                } else {
+                    vec![trap(None, "invalid user name or password (6)")]
+                }
+            }
+            None if !logged_in => vec![trap(None, "not logged in")],
+            None => {
+                let mut state = state.lock().unwrap();
+                state.commands.push(command.clone());
+                execute(&mut state, &command, &request)
+            }
+        };
+        if !matches!(
+            replies.last().and_then(|sentence| sentence.first()),
+            Some(ApiWord::Reply(ApiReplyType::Done))
+        ) {
+            replies.push(vec![ApiWord::Reply(ApiReplyType::Done)]);
+        }
+        for mut sentence in replies {
+            if let Some(tag) = &tag {
+                sentence.push(ApiWord::tag(tag));
+            }
+            if framed.send(sentence).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// `!fatal` followed by the reason as plain text like RouterOS sends it
+async fn send_fatal(
+    framed: &mut Framed<TcpStream, SentenceCodec>,
+    reason: &str,
+) -> Result<(), RosError> {
+    let mut buffer = BytesMut::new();
+    let mut words = WordCodec::new();
+    for word in [&b"!fatal"[..], reason.as_bytes(), b""] {
+        words.encode(word, &mut buffer)?;
+    }
+    framed.flush().await?;
+    let stream = framed.get_mut();
+    stream.write_all(&buffer).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn trap(category: Option<u32>, message: &str) -> Sentence {
+    let mut sentence = vec![ApiWord::Reply(ApiReplyType::Trap)];
+    if let Some(category) = category {
+        sentence.push(ApiWord::attribute("category", category));
+    }
+    sentence.push(ApiWord::attribute("message", message));
+    sentence
+}
+
+fn attributes(request: &[ApiWord]) -> Entry {
+    request
+        .iter()
+        .filter_map(|word| match word {
+            ApiWord::Attribute { key, value } => Some((key.clone(), value.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Replies to `command`, `!done` is added if the last one is not `!done` yet
+fn execute(state: &mut State, command: &str, request: &[ApiWord]) -> Vec<Sentence> {
+    let (path, action) = command.rsplit_once('/').unwrap_or(("", command));
+    let mut attributes = attributes(request);
+    match action {
+        "print" => {
+            let queries: Vec<Query> = request
+                .iter()
+                .filter_map(|word| match word {
+                    ApiWord::Query(query) => Some(query.clone()),
+                    _ => None,
+                })
+                .collect();
+            let proplist = attributes
+                .get(".proplist")
+                .map(|proplist| proplist.split(',').collect::<Vec<_>>());
+            state
+                .menus
+                .get(path)
+                .into_iter()
+                .flatten()
+                .filter(|entry| evaluate(&queries, |key| entry.get(key).cloned()))
+                .map(|entry| {
+                    let mut sentence = vec![ApiWord::Reply(ApiReplyType::Data)];
+                    sentence.extend(
+                        entry
+                            .iter()
+                            .filter(|(key, _)| {
+                                proplist
+                                    .as_ref()
+                                    .is_none_or(|names| names.contains(&key.as_str()))
+                            })
+                            .map(|(key, value)| ApiWord::attribute(key, value)),
+                    );
+                    sentence
+                })
+                .collect()
+        }
+        "add" => {
+            attributes.retain(|_, value| !value.is_empty());
+            let before = attributes.remove("place-before");
+            let id = state.insert(path, attributes);
+            if let Some(before) = before {
+                let entries = state.menus.entry(path.to_owned()).or_default();
+                let Some(destination) = position(entries, &before) else {
+                    entries.pop();
+                    return vec![trap(None, "no such item")];
+                };
+                let entry = entries.pop().unwrap_or_default();
+                entries.insert(destination, entry);
+            }
+            vec![vec![
+                ApiWord::Reply(ApiReplyType::Done),
+                ApiWord::attribute("ret", id),
+            ]]
+        }
+        "set" => {
+            let id = attributes
+                .remove(".id")
+                .or_else(|| attributes.remove("numbers"));
+            let entries = state.menus.entry(path.to_owned()).or_default();
+            let entry = match id {
+                Some(id) => match position(entries, &id) {
+                    Some(position) => entries.get_mut(position),
+                    None => None,
+                },
+                // single resources like `system/identity` have no id
+                None if entries.is_empty() => {
+                    entries.push(Entry::new());
+                    entries.last_mut()
+                }
+                None => entries.first_mut(),
+            };
+            match entry {
+                Some(entry) => {
+                    for (key, value) in attributes {
+                        if value.is_empty() {
+                            entry.remove(&key);
+                        } else {
+                            entry.insert(key, value);
+                        }
+                    }
+                    Vec::new()
+                }
+                None => vec![trap(None, "no such item")],
+            }
+        }
+        "remove" => {
+            let ids = attributes
+                .get(".id")
+                .or_else(|| attributes.get("numbers"))
+                .cloned()
+                .unwrap_or_default();
+            let entries = state.menus.entry(path.to_owned()).or_default();
+            for id in ids.split(',') {
+                match position(entries, id) {
+                    Some(position) => {
+                        entries.remove(position);
+                    }
+                    None => return vec![trap(None, "no such item")],
+                }
+            }
+            Vec::new()
+        }
+        "move" => {
+            let entries = state.menus.entry(path.to_owned()).or_default();
+            let id = attributes.get("numbers").cloned().unwrap_or_default();
+            let Some(from) = position(entries, &id) else {
+                return vec![trap(None, "no such item")];
+            };
+            let entry = entries.remove(from);
+            let destination = match attributes.get("destination") {
+                Some(destination) => match position(entries, destination) {
+                    Some(destination) => destination,
+                    None => {
+                        entries.insert(from, entry);
+                        return vec![trap(None, "no such item")];
+                    }
+                },
+                None => entries.len(),
+            };
+            entries.insert(destination, entry);
+            Vec::new()
+        }
+        "cancel" if path.is_empty() => Vec::new(),
+        _ => vec![trap(Some(0), "no such command")],
+    }
+}
@@ -0,0 +1,426 @@
+//! Discovery of menus and their attributes with `/console/inspect`, written in the format
+//! of the `ros_model` files.
+//!
+//! Types are inferred from the syntax descriptions the router sends for the arguments of
+//! `set`, the types of attributes that can only be read from the values `print` reports.
+//! The result is a starting point for the model files and should be reviewed, e.g. for
+//! additional key fields.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::net::IpAddr;
+use std::time::Duration;
+
+use ipnet::IpNet;
+use mac_address::MacAddress;
+
+use crate::client::api::{check_replies, ApiClient, ApiReplyType, ApiWord, Sentence};
+use crate::model::RosValue;
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Type of an attribute as written in `ros_model`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldType {
+    /// Kept as `String`, written without type
+    Text,
+    /// Type known to the model, e.g. `bool`, `u16` or `Auto<Duration>`
+    Value(String),
+    /// One of the values, generates an enum
+    Enum(Vec<String>),
+    /// Any number of the values, generates a `HashSet` of an enum
+    EnumSet(Vec<String>),
+}
+
+/// Attribute of a menu, one line of a `ros_model` file
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldSchema {
+    pub name: String,
+    pub is_id: bool,
+    pub is_read_only: bool,
+    pub field_type: FieldType,
+}
+
+/// Menu with its attributes, one section of a `ros_model` file
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct MenuSchema {
+    /// Path without leading `/`, e.g. `ip/address`
+    pub path: String,
+    /// Menu without entries like `system/identity`, written with a leading `1`
+    pub is_single: bool,
+    pub fields: Vec<FieldSchema>,
+}
+
+impl Display for FieldSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_read_only {
+            f.write_str("!")?;
+        }
+        if self.is_id {
+            f.write_str("*")?;
+        }
+        f.write_str(&self.name)?;
+        match &self.field_type {
+            FieldType::Text => Ok(()),
+            FieldType::Value(value_type) => write!(f, ": {value_type}"),
+            FieldType::Enum(values) => write!(f, ": {}", values.join(", ")),
+            FieldType::EnumSet(values) => write!(f, ": [{}]", values.join(",")),
+        }
+    }
+}
+
+impl Display for MenuSchema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_single {
+            f.write_str("1")?;
+        }
+        writeln!(f, "/{}", self.path)?;
+        for field in &self.fields {
+            writeln!(f, "{field}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Infers the type of an attribute from the definitions of its syntax, e.g.
+/// `yes | no` or `integer number 0..65535`
+pub fn infer_type(definitions: &[String]) -> FieldType {
+    let alternatives: Vec<&str> = definitions
+        .iter()
+        .flat_map(|definition| definition.split('|'))
+        .map(str::trim)
+        .filter(|alternative| !alternative.is_empty())
+        .collect();
+    match alternatives.as_slice() {
+        [] => FieldType::Text,
+        ["yes", "no"] | ["no", "yes"] | ["true", "false"] | ["false", "true"] => {
+            FieldType::Value(String::from("bool"))
+        }
+        [single] => value_type(single).map_or(FieldType::Text, FieldType::Value),
+        _ => {
+            let others: Vec<&str> = alternatives
+                .iter()
+                .copied()
+                .filter(|alternative| *alternative != "auto")
+                .collect();
+            match others.as_slice() {
+                [single] if others.len() < alternatives.len() => {
+                    if let Some(value_type) = value_type(single) {
+                        return FieldType::Value(format!("Auto<{value_type}>"));
+                    }
+                }
+                _ => {}
+            }
+            enum_type(&alternatives)
+        }
+    }
+}
+
+fn value_type(definition: &str) -> Option<String> {
+    let lower = definition.to_lowercase();
+    if lower.contains("mac address") {
+        return Some(String::from("MacAddress"));
+    }
+    if lower.contains("ip prefix") || lower.contains("a.b.c.d/m") {
+        return Some(String::from("IpNet"));
+    }
+    if lower.contains("ip address") || lower.contains("a.b.c.d") {
+        return Some(String::from("IpAddr"));
+    }
+    if lower == "time" || lower.starts_with("time interval") {
+        return Some(String::from("Duration"));
+    }
+    if let Some((min, max)) = integer_range(&lower) {
+        let integer_type = match (min, max) {
+            (0.., ..=0xFF) => "u8",
+            (0.., ..=0xFFFF) => "u16",
+            (0.., ..=0xFFFF_FFFF) => "u32",
+            (0.., _) => "u64",
+            (-128.., ..=127) => "i8",
+            _ => return None,
+        };
+        return Some(String::from(integer_type));
+    }
+    None
+}
+
+/// Bounds of the first `min..max` in `definition`
+fn integer_range(definition: &str) -> Option<(i128, i128)> {
+    let (start, end) = definition.split_once("..")?;
+    let min = start
+        .rsplit(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .next()?;
+    let max = end
+        .split(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .next()?;
+    Some((min.parse().ok()?, max.parse().ok()?))
+}
+
+/// Infers the type of a read-only attribute from the values `print` reported for it, e.g.
+/// `true` or `00:11:22:33:44:55`, all values have to agree
+pub fn infer_value_type<V: AsRef<str>>(values: &[V]) -> FieldType {
+    let all = |matches: fn(&str) -> bool| values.iter().all(|value| matches(value.as_ref()));
+    let value_type = if values.is_empty() {
+        return FieldType::Text;
+    } else if all(|value| value == "true" || value == "false") {
+        "bool"
+    } else if all(|value| value.parse::<u64>().is_ok()) {
+        "u64"
+    } else if all(|value| MacAddress::from_api(value).is_ok()) {
+        "MacAddress"
+    } else if all(|value| value.contains('/') && IpNet::from_api(value).is_ok()) {
+        "IpNet"
+    } else if all(|value| IpAddr::from_api(value).is_ok()) {
+        "IpAddr"
+    } else if all(is_duration) {
+        "Duration"
+    } else {
+        return FieldType::Text;
+    };
+    FieldType::Value(String::from(value_type))
+}
+
+/// Durations as `print` reports them, e.g. `1w2d3h4m5s` or `00:01:30`
+fn is_duration(value: &str) -> bool {
+    value.starts_with(|c: char| c.is_ascii_digit())
+        && value.contains(['w', 'd', 'h', 'm', 's', ':'])
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, 'w' | 'd' | 'h' | 'm' | 's' | ':' | '.'))
+        && Duration::from_api(value).is_ok()
+}
+
+/// Values that can be written as enum, the last one may allow a list like `value[,value]`
+fn enum_type(alternatives: &[&str]) -> FieldType {
+    let mut is_set = false;
+    let mut values = Vec::with_capacity(alternatives.len());
+    for alternative in alternatives {
+        let value = match alternative.split_once('[') {
+            Some((value, rest)) if rest.starts_with(',') => {
+                is_set = true;
+                value
+            }
+            Some(_) => return FieldType::Text,
+            None => alternative,
+        };
+        let is_valid = value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '/'));
+        if !is_valid || !value.chars().any(|c| c.is_ascii_alphabetic()) {
+            return FieldType::Text;
+        }
+        values.push(value.to_owned());
+    }
+    if is_set {
+        FieldType::EnumSet(values)
+    } else {
+        FieldType::Enum(values)
+    }
+}
+
+impl ApiClient {
+    /// Replies of `/console/inspect` for `request`, e.g. `child` or `syntax`, at `path`
+    pub async fn inspect(
+        &self,
+        request: &str,
+        path: &[&str],
+    ) -> Result<Vec<HashMap<String, String>>, RosError> {
+        let command = "console/inspect";
+        let sentences = self
+            .connection
+            .talk([
+                ApiWord::command(command),
+                ApiWord::attribute("request", request),
+                ApiWord::attribute("path", path.join(",")),
+            ])
+            .await?;
+        check_replies(command, &sentences)?;
+        Ok(data_rows(sentences))
+    }
+
+    /// Values `print` reports at `menu` for each of `names`
+    async fn print_values(
+        &self,
+        menu: &str,
+        names: &[&str],
+    ) -> Result<HashMap<String, Vec<String>>, RosError> {
+        let command = format!("{menu}/print");
+        let sentences = self
+            .connection
+            .talk([
+                ApiWord::command(&command),
+                ApiWord::attribute(".proplist", names.join(",")),
+            ])
+            .await?;
+        check_replies(&command, &sentences)?;
+        let mut ret: HashMap<String, Vec<String>> = HashMap::new();
+        for row in data_rows(sentences) {
+            for (key, value) in row {
+                ret.entry(key).or_default().push(value);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Describes the menu at `path`, e.g. `ip/firewall`, and all menus below it
+    pub async fn discover(&self, path: &str) -> Result<Vec<MenuSchema>, RosError> {
+        let mut ret = Vec::new();
+        let mut pending = vec![path.trim_matches('/').to_owned()];
+        while let Some(menu) = pending.pop() {
+            let path: Vec<&str> = menu.split('/').filter(|part| !part.is_empty()).collect();
+            let mut commands = Vec::new();
+            for child in self.inspect("child", &path).await? {
+                let (Some(name), Some(node_type)) = (child.get("name"), child.get("node-type"))
+                else {
+                    continue;
+                };
+                match node_type.as_str() {
+                    "dir" | "path" => pending.push(format!("{menu}/{name}")),
+                    "cmd" => commands.push(name.clone()),
+                    _ => {}
+                }
+            }
+            if commands.iter().any(|command| command == "print") {
+                let schema = self.describe(&menu, &path, &commands).await?;
+                if !schema.fields.is_empty() {
+                    ret.push(schema);
+                }
+            }
+        }
+        ret.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(ret)
+    }
+
+    async fn describe(
+        &self,
+        menu: &str,
+        path: &[&str],
+        commands: &[String],
+    ) -> Result<MenuSchema, RosError> {
+        let has = |command: &str| commands.iter().any(|c| c == command);
+        let mut properties = Vec::new();
+        if has("get") {
+            for definition in self
+                .definitions(&[path, &["get", "value-name"]].concat())
+                .await?
+            {
+                properties.extend(
+                    definition
+                        .split('|')
+                        .map(str::trim)
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_owned),
+                );
+            }
+        }
+        let write_command = if has("set") { "set" } else { "add" };
+        let mut writable = Vec::new();
+        if has(write_command) {
+            for child in self
+                .inspect("child", &[path, &[write_command]].concat())
+                .await?
+            {
+                match (child.get("name"), child.get("node-type")) {
+                    (Some(name), Some(node_type))
+                        if node_type == "arg" && name != "numbers" && name != ".id" =>
+                    {
+                        writable.push(name.clone())
+                    }
+                    _ => {}
+                }
+            }
+        }
+        for name in &writable {
+            if !properties.contains(name) {
+                properties.push(name.clone());
+            }
+        }
+        let is_single = !properties.iter().any(|name| name == ".id");
+        if let Some(position) = properties.iter().position(|name| name == ".id") {
+            let id = properties.remove(position);
+            properties.insert(0, id);
+        }
+
+        let read_only: Vec<&str> = properties
+            .iter()
+            .filter(|name| *name != ".id" && !writable.contains(name))
+            .map(String::as_str)
+            .collect();
+        let values = if read_only.is_empty() {
+            HashMap::new()
+        } else {
+            self.print_values(menu, &read_only).await?
+        };
+
+        let mut fields = Vec::with_capacity(properties.len());
+        for name in properties {
+            let is_read_only = !writable.contains(&name);
+            let field_type = if is_read_only {
+                values
+                    .get(&name)
+                    .map_or(FieldType::Text, |values| infer_value_type(values))
+            } else {
+                infer_type(
+                    &self
+                        .definitions(&[path, &[write_command, name.as_str()]].concat())
+                        .await?,
+                )
+            };
+            fields.push(FieldSchema {
+                // entries of menus with `.id` are also found by a writable `name`, as in
+                // `set bridge1 mtu=1500`
+                is_id: name == ".id"
+                    || name == "default-name"
+                    || (name == "name" && !is_read_only && !is_single),
+                is_read_only: is_read_only && name != ".id",
+                name,
+                field_type,
+            });
+        }
+        Ok(MenuSchema {
+            path: menu.to_owned(),
+            is_single,
+            fields,
+        })
+    }
+
+    /// Texts of the syntax definitions at `path`
+    async fn definitions(&self, path: &[&str]) -> Result<Vec<String>, RosError> {
+        let rows = self.inspect("syntax", path).await?;
+        let texts = |definitions_only: bool| -> Vec<String> {
+            rows.iter()
+                .filter(|row| {
+                    !definitions_only
+                        || row.get("symbol-type").map(String::as_str) == Some("definition")
+                })
+                .filter_map(|row| row.get("text").cloned())
+                .filter(|text| !text.is_empty())
+                .collect()
+        };
+        let definitions = texts(true);
+        Ok(if definitions.is_empty() {
+            texts(false)
+        } else {
+            definitions
+        })
+    }
+}
+
+/// Attributes of the data replies in `sentences`
+fn data_rows(sentences: Vec<Sentence>) -> Vec<HashMap<String, String>> {
+    sentences
+        .into_iter()
+        .filter(|sentence| sentence.first() == Some(&ApiWord::Reply(ApiReplyType::Data)))
+        .map(|sentence| {
+            sentence
+                .into_iter()
+                .filter_map(|word| match word {
+                    ApiWord::Attribute { key, value } => Some((key, value)),
+                    _ => None,
+                })
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use crate::client::api::inspect::{infer_type, infer_value_type, FieldType};
+use crate::client::api::{ApiClient, ApiConnection, ApiReplyType, ApiRos, ApiWord, LoginScheme};
+use crate::client::version::VersionFilter;
+use crate::model::extra::AttributeParser;
+
+fn infer(definitions: &[&str]) -> FieldType {
+    infer_type(
+        &definitions
+            .iter()
+            .map(|definition| String::from(*definition))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn value(value_type: &str) -> FieldType {
+    FieldType::Value(String::from(value_type))
+}
+
+#[test]
+fn infers_types_from_syntax() {
+    assert_eq!(infer(&["yes | no"]), value("bool"));
+    assert_eq!(infer(&["integer number 0..65535"]), value("u16"));
+    assert_eq!(infer(&["0..4294967295"]), value("u32"));
+    assert_eq!(infer(&["-10..10"]), value("i8"));
+    assert_eq!(infer(&["time interval"]), value("Duration"));
+    assert_eq!(infer(&["Time interval 0..4294967295"]), value("Duration"));
+    assert_eq!(infer(&["time"]), value("Duration"));
+    assert_eq!(infer(&["Date and time"]), FieldType::Text);
+    assert_eq!(infer(&["Timeout name"]), FieldType::Text);
+    assert_eq!(infer(&["IP prefix A.B.C.D/M"]), value("IpNet"));
+    assert_eq!(infer(&["IP address A.B.C.D"]), value("IpAddr"));
+    assert_eq!(infer(&["MAC address"]), value("MacAddress"));
+    assert_eq!(infer(&["auto | 0..65535"]), value("Auto<u16>"));
+    assert_eq!(
+        infer(&["disabled | enabled | proxy-arp"]),
+        FieldType::Enum(vec![
+            String::from("disabled"),
+            String::from("enabled"),
+            String::from("proxy-arp")
+        ])
+    );
+    assert_eq!(
+        infer(&["clientid | hostname[,Option]"]),
+        FieldType::EnumSet(vec![String::from("clientid"), String::from("hostname")])
+    );
+    assert_eq!(infer(&["Interface name"]), FieldType::Text);
+    assert_eq!(infer(&["text with spaces | other"]), FieldType::Text);
+}
+
+#[test]
+fn infers_types_from_values() {
+    assert_eq!(infer_value_type(&["true", "false"]), value("bool"));
+    assert_eq!(infer_value_type(&["1500", "0"]), value("u64"));
+    assert_eq!(
+        infer_value_type(&["00:0C:42:01:02:03"]),
+        value("MacAddress")
+    );
+    assert_eq!(infer_value_type(&["10.0.0.0/8"]), value("IpNet"));
+    assert_eq!(infer_value_type(&["10.0.0.1", "fe80::1"]), value("IpAddr"));
+    assert_eq!(
+        infer_value_type(&["1w2d3h4m5s", "00:01:30"]),
+        value("Duration")
+    );
+    assert_eq!(infer_value_type(&["true", "ether1"]), FieldType::Text);
+    assert_eq!(infer_value_type(&["ether1"]), FieldType::Text);
+    assert_eq!(infer_value_type::<&str>(&[]), FieldType::Text);
+}
+
+/// Name and node type of children or symbol type and text of syntax rows
+type Rows = &'static [(&'static str, &'static str)];
+
+/// Answers of the router by request and path
+const INSPECT: [(&str, &str, Rows); 10] = [
+    ("child", "interface", &[("bridge", "dir"), ("print", "cmd")]),
+    (
+        "child",
+        "interface,bridge",
+        &[
+            ("print", "cmd"),
+            ("get", "cmd"),
+            ("set", "cmd"),
+            ("add", "cmd"),
+        ],
+    ),
+    (
+        "syntax",
+        "interface,bridge,get,value-name",
+        &[("definition", ".id | name | mtu | running | arp")],
+    ),
+    (
+        "child",
+        "interface,bridge,set",
+        &[
+            ("numbers", "arg"),
+            ("name", "arg"),
+            ("mtu", "arg"),
+            ("arp", "arg"),
+        ],
+    ),
+    (
+        "syntax",
+        "interface,bridge,set,name",
+        &[("explanation", "Name")],
+    ),
+    (
+        "syntax",
+        "interface,bridge,set,mtu",
+        &[("definition", "auto | integer 0..65535")],
+    ),
+    (
+        "syntax",
+        "interface,bridge,set,arp",
+        &[("definition", "disabled | enabled | reply-only")],
+    ),
+    ("child", "system", &[("identity", "dir")]),
+    (
+        "child",
+        "system,identity",
+        &[("print", "cmd"), ("set", "cmd")],
+    ),
+    ("child", "system,identity,set", &[("name", "arg")]),
+];
+
+/// Values `print` reports for the read-only attributes by menu
+const PRINT: [(&str, Rows); 1] = [(
+    "interface/bridge/print",
+    &[("running", "true"), ("running", "false")],
+)];
+
+async fn router() -> ApiClient {
+    let (client_side, server_side) = tokio::io::duplex(4096);
+    let mut server = ApiRos::new(server_side);
+    tokio::spawn(async move {
+        while let Ok(request) = server.read_sentence().await {
+            let attribute = |name: &str| {
+                request.iter().find_map(|word| match word {
+                    ApiWord::Attribute { key, value } if key == name => Some(value.clone()),
+                    ApiWord::ApiAttribute { key, value } if key == name => Some(value.clone()),
+                    _ => None,
+                })
+            };
+            let tag = attribute("tag").unwrap();
+            if let Some((_, rows)) = PRINT
+                .iter()
+                .find(|(command, _)| request.first() == Some(&ApiWord::command(command)))
+            {
+                assert_eq!(attribute(".proplist").as_deref(), Some("running"));
+                for (key, value) in rows.iter() {
+                    let sentence = [
+                        ApiWord::Reply(ApiReplyType::Data),
+                        ApiWord::attribute(key, value),
+                        ApiWord::tag(&tag),
+                    ];
+                    server.write_sentence(sentence.into_iter()).await.unwrap();
+                }
+                let done = [ApiWord::Reply(ApiReplyType::Done), ApiWord::tag(&tag)];
+                server.write_sentence(done.into_iter()).await.unwrap();
+                continue;
+            }
+            let (request_type, path) = (attribute("request").unwrap(), attribute("path").unwrap());
+            let rows = INSPECT
+                .iter()
+                .find(|(r, p, _)| *r == request_type && *p == path)
+                .map(|(_, _, rows)| *rows)
+                .unwrap_or_default();
+            for (first, second) in rows {
+                let row = if request_type == "child" {
+                    [("name", first), ("node-type", second)]
+                } else {
+                    [("symbol-type", first), ("text", second)]
+                };
+                let sentence = std::iter::once(ApiWord::Reply(ApiReplyType::Data))
+                    .chain(
+                        row.iter()
+                            .map(|(key, value)| ApiWord::attribute(key, value)),
+                    )
+                    .chain([ApiWord::tag(&tag)]);
+                server.write_sentence(sentence).await.unwrap();
+            }
+            let done = [ApiWord::Reply(ApiReplyType::Done), ApiWord::tag(&tag)];
+            server.write_sentence(done.into_iter()).await.unwrap();
+        }
+    });
+    ApiClient {
+        connection: Arc::new(ApiConnection::start(ApiRos::new(client_side))),
+        login_scheme: LoginScheme::Plain,
+        versions: VersionFilter::default(),
+        parser: AttributeParser::default(),
+    }
+}
+
+#[tokio::test]
+async fn discovers_menus_in_model_format() {
+    let client = router().await;
+    let menus = client.discover("/interface").await.unwrap();
+    let model: Vec<String> = menus.iter().map(|menu| menu.to_string()).collect();
+    assert_eq!(
+        model,
+        ["/interface/bridge\n\
+             *.id\n\
+             *name\n\
+             mtu: Auto<u16>\n\
+             !running: bool\n\
+             arp: disabled, enabled, reply-only\n"]
+    );
+
+    let menus = client.discover("system").await.unwrap();
+    assert_eq!(menus[0].to_string(), "1/system/identity\nname\n");
+}
@@ -0,0 +1,303 @@
+//! Capturing and replaying api sessions to reproduce issues seen on a router.
+//!
+//! A capture holds one JSON object per line, `{"sent": [...]}` or `{"received": [...]}`,
+//! with the words of one sentence each. Passwords sent by `/login` are replaced by
+//! [`REDACTED`], so captures can be attached to bug reports.
+//!
+//! ```ignore
+//! let stream = Transport::Plain.open(target).await?;
+//! let stream = RecordingStream::create(stream, "capture.jsonl")?;
+//! let client = ApiClient::from_stream(stream, username, password).await?;
+//!
+//! // later, e.g. in a test
+//! let stream = ReplayStream::open("capture.jsonl")?;
+//! let client = ApiClient::from_stream(stream, username, password).await?;
+//! ```
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::pin::Pin;
+use std::task::{Context, Poll, Waker};
+
+use bytes::{Buf, BytesMut};
+use serde_json::{json, Value};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::client::api::codec::WordCodec;
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Value recorded instead of the credentials sent by `/login`
+pub const REDACTED: &str = "<redacted>";
+
+const SENT: &str = "sent";
+const RECEIVED: &str = "received";
+
+/// Splits the bytes of one direction of a session into sentences
+#[derive(Default)]
+struct Sentences {
+    buffer: BytesMut,
+    words: WordCodec,
+    sentence: Vec<String>,
+}
+
+impl Sentences {
+    fn push(&mut self, bytes: &[u8]) -> io::Result<Vec<Vec<String>>> {
+        self.buffer.extend_from_slice(bytes);
+        let mut ret = Vec::new();
+        while let Some(word) = self.words.decode(&mut self.buffer).map_err(invalid_data)? {
+            if word.is_empty() {
+                ret.push(std::mem::take(&mut self.sentence));
+            } else {
+                self.sentence.push(String::from_utf8_lossy(&word).into());
+            }
+        }
+        Ok(ret)
+    }
+}
+
+fn invalid_data<E: ToString>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+fn redacted(mut sentence: Vec<String>) -> Vec<String> {
+    if sentence.first().map(String::as_str) == Some("/login") {
+        for word in sentence.iter_mut() {
+            for key in ["=password=", "=response="] {
+                if word.starts_with(key) {
+                    *word = format!("{key}{REDACTED}");
+                }
+            }
+        }
+    }
+    sentence
+}
+
+/// Byte stream that writes every sentence exchanged over `inner` to a capture
+pub struct RecordingStream<S> {
+    inner: S,
+    capture: Box<dyn Write + Send + Sync>,
+    sent: Sentences,
+    received: Sentences,
+}
+
+impl<S> RecordingStream<S> {
+    pub fn new<W>(inner: S, capture: W) -> RecordingStream<S>
+    where
+        W: Write + Send + Sync + 'static,
+    {
+        RecordingStream {
+            inner,
+            capture: Box::new(capture),
+            sent: Sentences::default(),
+            received: Sentences::default(),
+        }
+    }
+
+    /// Records into a new file at `path`
+    pub fn create<P: AsRef<Path>>(inner: S, path: P) -> Result<RecordingStream<S>, RosError> {
+        Ok(Self::new(inner, BufWriter::new(File::create(path)?)))
+    }
+
+    fn record(&mut self, direction: &str, sentences: Vec<Vec<String>>) -> io::Result<()> {
+        for sentence in sentences {
+            let line = json!({ direction: redacted(sentence) });
+            writeln!(self.capture, "{line}")?;
+        }
+        self.capture.flush()
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for RecordingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = result {
+            let sentences = self.received.push(&buf.filled()[filled..])?;
+            self.record(RECEIVED, sentences)?;
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for RecordingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            let sentences = self.sent.push(&buf[..written])?;
+            self.record(SENT, sentences)?;
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[derive(Debug)]
+enum Exchange {
+    Sent(Vec<String>),
+    Received(Vec<String>),
+}
+
+/// Byte stream that plays the router's side of a capture.
+///
+/// Every sentence written to the stream must be the next one sent in the capture, the
+/// replies recorded after it become readable once it was written. A different sentence
+/// fails the write with [`io::ErrorKind::InvalidData`] naming both sentences.
+pub struct ReplayStream {
+    exchanges: VecDeque<Exchange>,
+    sent: Sentences,
+    readable: BytesMut,
+    reader: Option<Waker>,
+}
+
+impl ReplayStream {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<ReplayStream, RosError> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(capture: R) -> Result<ReplayStream, RosError> {
+        let mut exchanges = VecDeque::new();
+        for line in capture.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value = serde_json::from_str(&line)?;
+            let words = |direction: &str| -> Option<Vec<String>> {
+                value
+                    .get(direction)?
+                    .as_array()?
+                    .iter()
+                    .map(|word| word.as_str().map(String::from))
+                    .collect()
+            };
+            exchanges.push_back(match (words(SENT), words(RECEIVED)) {
+                (Some(sentence), None) => Exchange::Sent(sentence),
+                (None, Some(sentence)) => Exchange::Received(sentence),
+                _ => {
+                    return Err(RosError::SimpleMessage(format!(
+                        "Invalid line in capture: {line}"
+                    )))
+                }
+            });
+        }
+        let mut ret = ReplayStream {
+            exchanges,
+            sent: Sentences::default(),
+            readable: BytesMut::new(),
+            reader: None,
+        };
+        ret.release_replies()?;
+        Ok(ret)
+    }
+
+    /// Whether every sentence of the capture was sent and read
+    pub fn is_finished(&self) -> bool {
+        self.exchanges.is_empty() && self.readable.is_empty()
+    }
+
+    fn release_replies(&mut self) -> io::Result<()> {
+        let mut words = WordCodec::new();
+        while let Some(Exchange::Received(sentence)) = self.exchanges.front() {
+            for word in sentence {
+                words
+                    .encode(word.as_bytes(), &mut self.readable)
+                    .map_err(invalid_data)?;
+            }
+            words
+                .encode(&[][..], &mut self.readable)
+                .map_err(invalid_data)?;
+            self.exchanges.pop_front();
+        }
+        if let Some(reader) = self.reader.take() {
+            reader.wake();
+        }
+        Ok(())
+    }
+
+    fn expect(&mut self, sentence: Vec<String>) -> io::Result<()> {
+        let expected = match self.exchanges.pop_front() {
+            Some(Exchange::Sent(expected)) => expected,
+            _ => {
+                return Err(invalid_data(format!(
+                    "Replay diverged, nothing more was sent in the capture but got {sentence:?}"
+                )))
+            }
+        };
+        let matches = expected.len() == sentence.len()
+            && expected.iter().zip(&sentence).all(|(expected, word)| {
+                match expected.strip_suffix(REDACTED) {
+                    Some(key) if expected.starts_with('=') => word.starts_with(key),
+                    _ => expected == word,
+                }
+            });
+        if !matches {
+            return Err(invalid_data(format!(
+                "Replay diverged, expected {expected:?} but got {sentence:?}"
+            )));
+        }
+        self.release_replies()
+    }
+}
+
+impl AsyncRead for ReplayStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.readable.is_empty() {
+            if self.exchanges.is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+            self.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        let len = self.readable.len().min(buf.remaining());
+        buf.put_slice(&self.readable[..len]);
+        self.readable.advance(len);
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for ReplayStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        for sentence in self.sent.push(buf)? {
+            self.expect(sentence)?;
+        }
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+use futures::{SinkExt, StreamExt};
+use tokio::io::duplex;
+use tokio_util::codec::Framed;
+
+use crate::client::api::codec::SentenceCodec;
+use crate::client::api::record::{RecordingStream, ReplayStream};
+use crate::client::api::{ApiClient, ApiReplyType, ApiRos, ApiWord};
+use crate::client::Client;
+use crate::generated::interface::bridge::Bridge;
+use crate::RosError;
+
+const CAPTURE: &str = r#"
+{"sent":["/login","=name=admin","=password=<redacted>"]}
+{"received":["!done"]}
+{"sent":["/interface/bridge/print",".tag=0"]}
+{"received":["!re","=.id=*1","=name=bridge1","=mtu=1500",".tag=0"]}
+{"received":["!done",".tag=0"]}
+"#;
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Capture {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn records_sentences_without_credentials() {
+    let (client_side, server_side) = duplex(4096);
+    let capture = Capture::default();
+    let mut api = ApiRos::new(RecordingStream::new(client_side, capture.clone()));
+    let mut server = Framed::new(server_side, SentenceCodec::new());
+    let router = tokio::spawn(async move {
+        server.next().await.unwrap().unwrap().unwrap();
+        server
+            .send(vec![ApiWord::Reply(ApiReplyType::Done)])
+            .await
+            .unwrap();
+    });
+    api.login(String::from("admin"), String::from("secret"))
+        .await
+        .unwrap();
+    router.await.unwrap();
+
+    let capture = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(
+        capture,
+        "{\"sent\":[\"/login\",\"=name=admin\",\"=password=<redacted>\"]}\n\
+         {\"received\":[\"!done\"]}\n"
+    );
+}
+
+#[tokio::test]
+async fn replays_capture() {
+    let stream = ReplayStream::from_reader(CAPTURE.as_bytes()).unwrap();
+    let mut client = ApiClient::from_stream(stream, String::from("admin"), String::from("x"))
+        .await
+        .unwrap();
+    let bridges: Vec<Bridge> = client.list().await.unwrap();
+    assert_eq!(bridges.len(), 1);
+    assert_eq!(bridges[0].name.get().as_deref(), Some("bridge1"));
+}
+
+#[tokio::test]
+async fn divergence_fails_the_request() {
+    let stream = ReplayStream::from_reader(CAPTURE.as_bytes()).unwrap();
+    let mut client = ApiClient::from_stream(stream, String::from("admin"), String::from("x"))
+        .await
+        .unwrap();
+    let mut bridge = Bridge::default();
+    bridge.name.set("bridge2");
+    let error = client.add(&mut bridge).await.unwrap_err();
+    assert!(
+        matches!(&error, RosError::TokioError(error) if error.to_string().contains("Replay diverged")),
+        "{error}"
+    );
+}
@@ -0,0 +1,247 @@
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::Stream;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::client::api::{
+    reply_error, ApiClient, ApiConnection, ApiReplyType, ApiWord, AttributeCollector, Sentence,
+    TrapCategory,
+};
+use crate::model::extra::AttributeParser;
+use crate::model::ros_value::RosValue;
+use crate::model::{RouterOsResource, ValueFormat};
+use crate::RosError;
+
+/// Change reported by a running `listen`, `print follow` or `monitor` command
+#[derive(Debug, Clone)]
+pub enum ResourceEvent<Resource> {
+    /// Current state of an entry that was added or changed
+    Update(Resource),
+    /// The entry with this `.id` was removed
+    Removed(String),
+}
+
+/// Continuous `!re` replies of a command, parsed into `Resource`.
+///
+/// The command runs until [`ResourceStream::cancel`] is called or the stream is dropped.
+pub struct ResourceStream<Resource: RouterOsResource> {
+    connection: Arc<ApiConnection>,
+    command: String,
+    tag: String,
+    receiver: UnboundedReceiver<Result<Sentence, RosError>>,
+    ignore_unknown_fields: bool,
+    parser: AttributeParser,
+    cancelled: bool,
+    finished: bool,
+    resource: PhantomData<fn() -> Resource>,
+}
+
+impl<Resource: RouterOsResource> ResourceStream<Resource> {
+    /// Tag of the running command
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Stops the command on the router with `/cancel`, the stream ends after the
+    /// remaining replies
+    pub async fn cancel(&mut self) -> Result<(), RosError> {
+        if !self.finished && !self.cancelled {
+            self.cancelled = true;
+            cancel_command(&self.connection, &self.tag).await?;
+        }
+        Ok(())
+    }
+
+    fn parse(&self, sentence: Sentence) -> Option<Result<ResourceEvent<Resource>, RosError>> {
+        if let Some(error) = reply_error(&self.command, &sentence) {
+            let interrupted = matches!(
+                error,
+                RosError::Trap {
+                    category: Some(TrapCategory::ExecutionInterrupted),
+                    ..
+                }
+            );
+            return if self.cancelled && interrupted {
+                None
+            } else {
+                Some(Err(error))
+            };
+        }
+        let mut words = sentence.into_iter();
+        let mut collector = match words.next() {
+            Some(ApiWord::Reply(ApiReplyType::Data)) => {
+                AttributeCollector::Ressource(Resource::default())
+            }
+            _ => return None,
+        };
+        let mut id = None;
+        let mut dead = false;
+        for word in words {
+            if let ApiWord::Attribute { key, value } = word {
+                match key.as_str() {
+                    ".dead" => {
+                        dead = value == "true" || value == "yes";
+                        continue;
+                    }
+                    ".id" => id = Some(value.clone()),
+                    _ => {}
+                }
+                let is_known = !self.ignore_unknown_fields
+                    || match &collector {
+                        AttributeCollector::Ressource(resource) => resource
+                            .fields()
+                            .any(|(description, _)| description.name == key),
+                        AttributeCollector::None => true,
+                    };
+                if is_known {
+                    if let Err(error) = collector.write_attribute(key, value, &self.parser) {
+                        return Some(Err(error));
+                    }
+                }
+            }
+        }
+        match collector.extract() {
+            Some(_) if dead => id.map(|id| Ok(ResourceEvent::Removed(id))),
+            Some(resource) => Some(Ok(ResourceEvent::Update(resource))),
+            None => None,
+        }
+    }
+}
+
+impl<Resource: RouterOsResource> Stream for ResourceStream<Resource> {
+    type Item = Result<ResourceEvent<Resource>, RosError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if self.finished {
+                return Poll::Ready(None);
+            }
+            let sentence = match self.receiver.poll_recv(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => {
+                    self.finished = true;
+                    return Poll::Ready(None);
+                }
+                Poll::Ready(Some(Err(error))) => {
+                    // a malformed sentence only costs its event
+                    self.finished = error.is_connection_error();
+                    return Poll::Ready(Some(Err(error)));
+                }
+                Poll::Ready(Some(Ok(sentence))) => sentence,
+            };
+            if sentence.first() == Some(&ApiWord::Reply(ApiReplyType::Done)) {
+                self.finished = true;
+                return Poll::Ready(None);
+            }
+            if let Some(event) = self.parse(sentence) {
+                return Poll::Ready(Some(event));
+            }
+        }
+    }
+}
+
+impl<Resource: RouterOsResource> Drop for ResourceStream<Resource> {
+    fn drop(&mut self) {
+        if self.finished || self.cancelled {
+            return;
+        }
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let connection = self.connection.clone();
+            let tag = self.tag.clone();
+            runtime.spawn(async move {
+                let _ = cancel_command(&connection, &tag).await;
+            });
+        }
+    }
+}
+
+async fn cancel_command(connection: &ApiConnection, tag: &str) -> Result<(), RosError> {
+    connection
+        .talk([ApiWord::command("cancel"), ApiWord::attribute("tag", tag)])
+        .await?;
+    Ok(())
+}
+
+impl ApiClient {
+    /// Runs `listen` on the menu of `Resource` and reports every change
+    pub async fn listen<Resource: RouterOsResource>(
+        &self,
+    ) -> Result<ResourceStream<Resource>, RosError> {
+        self.stream(vec![ApiWord::command(format!(
+            "{}/listen",
+            Resource::resource_path()
+        ))])
+        .await
+    }
+
+    /// Runs `print follow`, which reports all current entries followed by every change
+    pub async fn follow<Resource: RouterOsResource>(
+        &self,
+    ) -> Result<ResourceStream<Resource>, RosError> {
+        self.print_stream::<Resource>("follow").await
+    }
+
+    /// Runs `print follow-only`, which reports only changes after the command started
+    pub async fn follow_only<Resource: RouterOsResource>(
+        &self,
+    ) -> Result<ResourceStream<Resource>, RosError> {
+        self.print_stream::<Resource>("follow-only").await
+    }
+
+    /// Runs `monitor` on the entries given by `numbers` (ids or names, comma separated).
+    ///
+    /// Only attributes that are fields of `Resource` are kept, the remaining monitor
+    /// values are skipped.
+    pub async fn monitor<Resource: RouterOsResource>(
+        &self,
+        numbers: &str,
+        interval: Duration,
+    ) -> Result<ResourceStream<Resource>, RosError> {
+        let mut stream = self
+            .stream(vec![
+                ApiWord::command(format!("{}/monitor", Resource::resource_path())),
+                ApiWord::attribute("numbers", numbers),
+                ApiWord::attribute("interval", interval.to_api(&ValueFormat::Api)),
+            ])
+            .await?;
+        stream.ignore_unknown_fields = true;
+        Ok(stream)
+    }
+
+    async fn print_stream<Resource: RouterOsResource>(
+        &self,
+        mode: &str,
+    ) -> Result<ResourceStream<Resource>, RosError> {
+        self.stream(vec![
+            ApiWord::command(format!("{}/print", Resource::resource_path())),
+            ApiWord::attribute(mode, ""),
+        ])
+        .await
+    }
+
+    async fn stream<Resource: RouterOsResource>(
+        &self,
+        words: Vec<ApiWord>,
+    ) -> Result<ResourceStream<Resource>, RosError> {
+        let command = match words.first() {
+            Some(ApiWord::Command(command)) => command.clone(),
+            _ => String::new(),
+        };
+        let (tag, receiver) = self.connection.request(words).await?;
+        Ok(ResourceStream {
+            connection: self.connection.clone(),
+            command,
+            tag,
+            receiver,
+            ignore_unknown_fields: false,
+            parser: self.parser.clone(),
+            cancelled: false,
+            finished: false,
+            resource: PhantomData,
+        })
+    }
+}
@@ -0,0 +1,406 @@
+use std::iter::once;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bytes::BytesMut;
+use futures::StreamExt;
+use proptest::prelude::*;
+use tokio::io::AsyncWriteExt;
+use tokio_util::codec::Encoder;
+
+use crate::client::api::codec::WordCodec;
+use crate::client::api::stream::ResourceEvent;
+use crate::client::api::{
+    challenge_response, decode_hex, reply_error, ApiClient, ApiConnection, ApiReplyType, ApiRos,
+    ApiWord, LoginScheme, TrapCategory,
+};
+use crate::client::filter::Query;
+use crate::client::version::VersionFilter;
+use crate::client::Client;
+use crate::generated::ip::address::Address;
+use crate::model::extra::AttributeParser;
+use crate::RosError;
+
+#[test]
+fn check_challenge_response() {
+    let challenge = decode_hex("ebddd18775b8b0f6d8e2b2a8a0d8c2a1").unwrap();
+    assert_eq!(
+        challenge_response("secret", &challenge),
+        "004fc9268c429dd3f8392b3f303d718dd3"
+    );
+    assert_eq!(decode_hex("abc"), None);
+    assert_eq!(decode_hex("zz"), None);
+}
+
+fn tag_of(sentence: &[ApiWord]) -> String {
+    sentence
+        .iter()
+        .find_map(|word| match word {
+            ApiWord::ApiAttribute { key, value } if key == "tag" => Some(value.clone()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn replies_are_dispatched_by_tag() {
+    let (client_side, server_side) = tokio::io::duplex(4096);
+    let connection = ApiConnection::start(ApiRos::new(client_side));
+    let mut server = ApiRos::new(server_side);
+
+    let server = tokio::spawn(async move {
+        let first = server.read_sentence().await.unwrap();
+        let second = server.read_sentence().await.unwrap();
+        for (sentence, name) in [(second, "second"), (first, "first")] {
+            let tag = tag_of(&sentence);
+            server
+                .write_sentence(
+                    [
+                        ApiWord::Reply(ApiReplyType::Data),
+                        ApiWord::attribute("name", name),
+                        ApiWord::tag(&tag),
+                    ]
+                    .into_iter(),
+                )
+                .await
+                .unwrap();
+            server
+                .write_sentence(
+                    [ApiWord::Reply(ApiReplyType::Done), ApiWord::tag(&tag)].into_iter(),
+                )
+                .await
+                .unwrap();
+        }
+    });
+
+    let (first, second) = tokio::join!(
+        connection.talk([ApiWord::command("first/print")]),
+        connection.talk([ApiWord::command("second/print")])
+    );
+    server.await.unwrap();
+    assert_eq!(first.unwrap()[0][1], ApiWord::attribute("name", "first"));
+    assert_eq!(second.unwrap()[0][1], ApiWord::attribute("name", "second"));
+}
+
+#[tokio::test]
+async fn malformed_sentences_only_fail_their_request() {
+    let (client_side, server_side) = tokio::io::duplex(4096);
+    let connection = ApiConnection::start(ApiRos::new(client_side));
+    let mut server = ApiRos::new(server_side);
+
+    let server = tokio::spawn(async move {
+        let first = server.read_sentence().await.unwrap();
+        let second = server.read_sentence().await.unwrap();
+        let (first, second) = (tag_of(&first), tag_of(&second));
+        let mut buffer = BytesMut::new();
+        let mut words = WordCodec::new();
+        for word in [
+            &b"!re"[..],
+            b"=na\xffme=first",
+            format!(".tag={first}").as_bytes(),
+            b"",
+            b"!re",
+            b"=name=second",
+            format!(".tag={second}").as_bytes(),
+            b"",
+        ] {
+            words.encode(word, &mut buffer).unwrap();
+        }
+        server.framed.get_mut().write_all(&buffer).await.unwrap();
+        for tag in [&first, &second] {
+            server
+                .write_sentence([ApiWord::Reply(ApiReplyType::Done), ApiWord::tag(tag)].into_iter())
+                .await
+                .unwrap();
+        }
+        let third = server.read_sentence().await.unwrap();
+        server
+            .write_sentence(
+                [
+                    ApiWord::Reply(ApiReplyType::Done),
+                    ApiWord::tag(tag_of(&third)),
+                ]
+                .into_iter(),
+            )
+            .await
+            .unwrap();
+    });
+
+    let (first, second) = tokio::join!(
+        connection.talk([ApiWord::command("first/print")]),
+        connection.talk([ApiWord::command("second/print")])
+    );
+    assert!(matches!(first, Err(RosError::ProtocolError { .. })));
+    assert_eq!(second.unwrap()[0][1], ApiWord::attribute("name", "second"));
+    connection
+        .talk([ApiWord::command("third/print")])
+        .await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn values_in_the_router_codepage_are_listed() {
+    let (client_side, server_side) = tokio::io::duplex(4096);
+    let mut client = ApiClient {
+        connection: Arc::new(ApiConnection::start(ApiRos::new(client_side))),
+        login_scheme: LoginScheme::Plain,
+        versions: VersionFilter::default(),
+        parser: AttributeParser::default(),
+    };
+    let mut server = ApiRos::new(server_side);
+
+    let server = tokio::spawn(async move {
+        let request = server.read_sentence().await.unwrap();
+        assert_eq!(request[0], ApiWord::command("ip/address/print"));
+        let tag = tag_of(&request);
+        let mut buffer = BytesMut::new();
+        let mut words = WordCodec::new();
+        for word in [
+            &b"!re"[..],
+            b"=.id=*1",
+            b"=comment=caf\xe9",
+            format!(".tag={tag}").as_bytes(),
+            b"",
+            b"!done",
+            format!(".tag={tag}").as_bytes(),
+            b"",
+        ] {
+            words.encode(word, &mut buffer).unwrap();
+        }
+        server.framed.get_mut().write_all(&buffer).await.unwrap();
+    });
+
+    let addresses: Vec<Address> = client.list().await.unwrap();
+    server.await.unwrap();
+    assert_eq!(addresses.len(), 1);
+    assert_eq!(addresses[0].comment.get().as_deref(), Some("caf\u{e9}"));
+}
+
+#[tokio::test]
+async fn cancelled_requests_write_whole_sentences() {
+    let (client_side, server_side) = tokio::io::duplex(64);
+    let connection = ApiConnection::start(ApiRos::new(client_side));
+    let mut server = ApiRos::new(server_side);
+
+    let comment = "x".repeat(4096);
+    let cancelled = tokio::time::timeout(
+        Duration::from_millis(20),
+        connection.talk([
+            ApiWord::command("ip/address/set"),
+            ApiWord::attribute("comment", &comment),
+        ]),
+    )
+    .await;
+    assert!(cancelled.is_err());
+    // written completely without waiting for the next request
+    let first = tokio::time::timeout(Duration::from_secs(1), server.read_sentence())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(first[1], ApiWord::attribute("comment", comment));
+
+    let server = tokio::spawn(async move {
+        let second = server.read_sentence().await.unwrap();
+        assert_eq!(second[0], ApiWord::command("ip/address/print"));
+        server
+            .write_sentence(
+                [
+                    ApiWord::Reply(ApiReplyType::Done),
+                    ApiWord::tag(tag_of(&second)),
+                ]
+                .into_iter(),
+            )
+            .await
+            .unwrap();
+    });
+    connection
+        .talk([ApiWord::command("ip/address/print")])
+        .await
+        .unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn follow_streams_changes_until_cancelled() {
+    let (client_side, server_side) = tokio::io::duplex(4096);
+    let client = ApiClient {
+        connection: Arc::new(ApiConnection::start(ApiRos::new(client_side))),
+        login_scheme: LoginScheme::Plain,
+        versions: VersionFilter::default(),
+        parser: AttributeParser::default(),
+    };
+    let mut server = ApiRos::new(server_side);
+
+    let server = tokio::spawn(async move {
+        let follow = server.read_sentence().await.unwrap();
+        assert_eq!(follow[0], ApiWord::command("ip/address/print"));
+        assert_eq!(follow[1], ApiWord::attribute("follow", ""));
+        let tag = tag_of(&follow);
+        for attributes in [
+            vec![("address", "10.0.0.1/24"), (".id", "*1")],
+            vec![(".id", "*1"), (".dead", "true")],
+        ] {
+            server
+                .write_sentence(
+                    once(ApiWord::Reply(ApiReplyType::Data))
+                        .chain(
+                            attributes
+                                .into_iter()
+                                .map(|(k, v)| ApiWord::attribute(k, v)),
+                        )
+                        .chain(once(ApiWord::tag(&tag))),
+                )
+                .await
+                .unwrap();
+        }
+        let cancel = server.read_sentence().await.unwrap();
+        assert_eq!(cancel[0], ApiWord::command("cancel"));
+        assert_eq!(cancel[1], ApiWord::attribute("tag", &tag));
+        let cancel_tag = tag_of(&cancel);
+        for (reply, tag) in [
+            (ApiReplyType::Trap, &tag),
+            (ApiReplyType::Done, &tag),
+            (ApiReplyType::Done, &cancel_tag),
+        ] {
+            server
+                .write_sentence(
+                    [
+                        ApiWord::Reply(reply),
+                        ApiWord::attribute("category", "2"),
+                        ApiWord::attribute("message", "interrupted"),
+                        ApiWord::tag(tag),
+                    ]
+                    .into_iter(),
+                )
+                .await
+                .unwrap();
+        }
+    });
+
+    let mut stream = client.follow::<Address>().await.unwrap();
+    match stream.next().await {
+        Some(Ok(ResourceEvent::Update(address))) => {
+            assert_eq!(address.address.to_string(), "10.0.0.1/24")
+        }
+        other => panic!("Unexpected event: {other:?}"),
+    }
+    match stream.next().await {
+        Some(Ok(ResourceEvent::Removed(id))) => assert_eq!(id, "*1"),
+        other => panic!("Unexpected event: {other:?}"),
+    }
+    stream.cancel().await.unwrap();
+    assert!(stream.next().await.is_none());
+    server.await.unwrap();
+}
+
+#[test]
+fn traps_keep_category_and_attributes() {
+    let trap = [
+        ApiWord::Reply(ApiReplyType::Trap),
+        ApiWord::attribute("category", "1"),
+        ApiWord::attribute("message", "invalid value for argument mtu"),
+        ApiWord::tag("4"),
+    ];
+    match reply_error("interface/bridge/set", &trap) {
+        Some(
+            error @ RosError::Trap {
+                category: Some(TrapCategory::ArgumentValueFailure),
+                ..
+            },
+        ) => {
+            assert_eq!(
+                error.to_string(),
+                "/interface/bridge/set failed (ArgumentValueFailure): invalid value for argument mtu"
+            );
+            if let RosError::Trap {
+                tag, attributes, ..
+            } = error
+            {
+                assert_eq!(tag.as_deref(), Some("4"));
+                assert_eq!(attributes.len(), 2);
+            }
+        }
+        other => panic!("Unexpected error: {other:?}"),
+    }
+    assert!(reply_error("ip/address/print", &[ApiWord::Reply(ApiReplyType::Done)]).is_none());
+}
+
+#[test]
+fn parse_reports_unknown_words() {
+    assert_eq!(ApiWord::parse(b"").unwrap(), None);
+    assert_eq!(
+        ApiWord::parse(b"!empty").unwrap(),
+        Some(ApiWord::Reply(ApiReplyType::Empty))
+    );
+    assert_eq!(
+        ApiWord::parse(b"?<distance=10").unwrap(),
+        Some(ApiWord::Query(Query::Lt {
+            key: String::from("distance"),
+            value: String::from("10")
+        }))
+    );
+    assert_eq!(
+        ApiWord::parse(b"=comment=a=b").unwrap(),
+        Some(ApiWord::attribute("comment", "a=b"))
+    );
+    assert_eq!(
+        ApiWord::parse(b"=comment=a=b\xff").unwrap(),
+        Some(ApiWord::attribute("comment", "a=b\u{ff}"))
+    );
+    for word in [
+        &b"session terminated"[..],
+        b"!unknown",
+        b"!re\xff",
+        b"?",
+        b"=comm\xffent=a",
+    ] {
+        assert!(matches!(
+            ApiWord::parse(word),
+            Err(RosError::ProtocolError { .. })
+        ));
+    }
+}
+
+fn key() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9.-]{0,15}"
+}
+
+fn api_word() -> impl Strategy<Value = ApiWord> {
+    let reply = prop_oneof![
+        Just(ApiReplyType::Done),
+        Just(ApiReplyType::Data),
+        Just(ApiReplyType::Trap),
+        Just(ApiReplyType::Fatal),
+        Just(ApiReplyType::Empty),
+    ];
+    let query = prop_oneof![
+        key().prop_map(Query::HasValue),
+        key().prop_map(Query::HasNoValue),
+        (key(), any::<String>()).prop_map(|(key, value)| Query::Equals { key, value }),
+        (key(), any::<String>()).prop_map(|(key, value)| Query::Lt { key, value }),
+        (key(), any::<String>()).prop_map(|(key, value)| Query::Gt { key, value }),
+        "[|&!.]{1,4}".prop_map(Query::Operations),
+    ];
+    prop_oneof![
+        any::<String>().prop_map(ApiWord::Command),
+        (key(), any::<String>()).prop_map(|(key, value)| ApiWord::Attribute { key, value }),
+        (key(), any::<String>()).prop_map(|(key, value)| ApiWord::ApiAttribute { key, value }),
+        query.prop_map(ApiWord::Query),
+        reply.prop_map(ApiWord::Reply),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn encoded_words_parse_to_themselves(word in api_word()) {
+        prop_assert_eq!(ApiWord::parse(&word.encode()).unwrap(), Some(word));
+    }
+
+    #[test]
+    fn parsed_words_encode_to_equal_words(bytes in proptest::collection::vec(any::<u8>(), 0..64)) {
+        if let Ok(Some(word)) = ApiWord::parse(&bytes) {
+            prop_assert_eq!(ApiWord::parse(&word.encode()).unwrap(), Some(word));
+        }
+    }
+}
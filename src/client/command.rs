@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+
+use crate::model::{RosValue, RouterOsResource, ValueFormat};
+
+/// Command of a menu other than the ones to edit entries, e.g. `/system/reboot` or
+/// `/interface/ethernet/reset-counters`.
+///
+/// ```ignore
+/// let ping = Command::new("tool", "ping")
+///     .argument("address", IpAddr::from([10, 0, 0, 1]))
+///     .argument("count", 3u32);
+/// let replies = client.execute(&ping).await?;
+/// ```
+///
+/// Commands that report until they are cancelled, like `/tool/ping` without `count`, never
+/// finish on [`crate::client::Client::execute`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Command {
+    menu: String,
+    name: String,
+    arguments: Vec<Argument>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct Argument {
+    key: String,
+    api_value: String,
+    cli_value: String,
+}
+
+impl Command {
+    /// Command `name` of the menu at `menu`, e.g. `Command::new("ip/dhcp-client", "renew")`
+    pub fn new<M, N>(menu: M, name: N) -> Command
+    where
+        M: Into<String>,
+        N: Into<String>,
+    {
+        Command {
+            menu: menu.into().trim_matches('/').to_owned(),
+            name: name.into(),
+            arguments: Vec::new(),
+        }
+    }
+    /// Command `name` of the menu of `Resource`
+    pub fn of<Resource: RouterOsResource>(name: impl Into<String>) -> Command {
+        Self::new(Resource::resource_path(), name)
+    }
+
+    pub fn argument<K, V>(mut self, key: K, value: V) -> Command
+    where
+        K: Into<String>,
+        V: RosValue,
+    {
+        self.arguments.push(Argument {
+            key: key.into(),
+            api_value: value.to_api(&ValueFormat::Api),
+            cli_value: value.to_api(&ValueFormat::Cli),
+        });
+        self
+    }
+
+    pub fn menu(&self) -> &str {
+        &self.menu
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Path of the command without leading `/`, e.g. `system/reboot`
+    pub fn path(&self) -> String {
+        if self.menu.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}/{}", self.menu, self.name)
+        }
+    }
+    /// Arguments with their value in `format`
+    pub fn arguments(&self, format: &ValueFormat) -> impl Iterator<Item = (&str, &str)> + '_ {
+        let cli = matches!(format, ValueFormat::Cli);
+        self.arguments.iter().map(move |argument| {
+            let value = if cli {
+                &argument.cli_value
+            } else {
+                &argument.api_value
+            };
+            (argument.key.as_str(), value.as_str())
+        })
+    }
+}
+
+/// Replies to a [`Command`]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct CommandOutput {
+    /// Attributes of each reply row, e.g. one per echo reply of `/tool/ping`
+    pub rows: Vec<HashMap<String, String>>,
+    /// Value returned by the command, e.g. the id of an entry or the result of a script
+    pub ret: Option<String>,
+}
@@ -1,19 +1,41 @@
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
 use std::future::ready;
 use std::mem::take;
 
 use async_trait::async_trait;
 
+use crate::client::command::{Command, CommandOutput};
+use crate::client::version::{UnsupportedFields, VersionFilter};
 use crate::client::Client;
 use crate::hardware::MikrotikModel;
-use crate::model::{RouterOsListResource, RouterOsResource, RouterOsSingleResource, ValueFormat};
+use crate::model::dynamic::{DynamicMenu, DynamicResource};
+use crate::model::extra::{AttributeParser, ParseMode, ParseWarning};
+use crate::model::version::RosVersion;
+use crate::model::{
+    FieldDescription, RosFieldAccessor, RouterOsListResource, RouterOsResource,
+    RouterOsSingleResource, ValueFormat,
+};
 use crate::RosError;
 
+#[cfg(test)]
+mod tests;
+
 pub struct ConfigClient {
     output: String,
-    model: HashMap<&'static str, Vec<HashMap<&'static str, String>>>,
-    current_context: &'static str,
+    model: HashMap<String, Vec<HashMap<String, String>>>,
+    current_context: String,
+    /// Last of the ids assigned to added entries, the generated script cannot refer to them
+    last_id: u32,
+    versions: VersionFilter,
+    parser: AttributeParser,
+}
+
+impl Default for ConfigClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ConfigClient {
@@ -21,51 +43,124 @@ impl ConfigClient {
         ConfigClient {
             output: String::new(),
             model: HashMap::new(),
-            current_context: "",
+            current_context: String::new(),
+            last_id: 0,
+            versions: VersionFilter::default(),
+            parser: AttributeParser::default(),
         }
     }
+    /// Writes scripts for routers running `version`, fields it does not support are not
+    /// written, see [`crate::client::version`]
+    pub fn with_version(mut self, version: RosVersion) -> ConfigClient {
+        self.versions.version = Some(version);
+        self
+    }
+    /// Sets what happens to modified fields the version does not support
+    pub fn unsupported_fields(mut self, unsupported: UnsupportedFields) -> ConfigClient {
+        self.versions.unsupported = unsupported;
+        self
+    }
+    /// Sets how stored attributes that do not fit the resources are treated, see
+    /// [`crate::model::extra`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> ConfigClient {
+        self.parser.mode = mode;
+        self
+    }
     pub async fn with_default_config(model: MikrotikModel) -> Result<ConfigClient, RosError> {
         let mut ret = Self::new();
         model.init(&mut ret).await?;
         Ok(ret)
     }
     pub fn dump_cmd(&mut self) -> String {
-        self.current_context = "";
+        self.current_context.clear();
         take(&mut self.output)
     }
-    fn ensure_context(&mut self, resource_path: &'static str) {
+    fn ensure_context(&mut self, resource_path: &str) {
         if resource_path != self.current_context {
             self.output.push('/');
             self.output
                 .push_str(resource_path.replace('/', " ").as_str());
             self.output.push('\n');
-            self.current_context = resource_path;
+            self.current_context = resource_path.to_owned();
         }
     }
-    fn append_modified_fields<Resource>(&mut self, resource: &Resource)
+    /// Modified fields as ` key=value` arguments of a script line
+    fn modified_fields<Resource>(&self, resource: &Resource) -> Result<String, RosError>
     where
         Resource: RouterOsResource,
     {
-        resource
-            .fields()
-            .filter_map(|f| {
-                f.1.modified_value(&ValueFormat::Cli)
-                    .map(|v| (f.0.name, quote_routeros(&v)))
-            })
-            .for_each(|(key, value)| self.output.push_str(&format!(" {key}={value}")));
+        Ok(self
+            .versions
+            .modified_values(resource, &ValueFormat::Cli)?
+            .into_iter()
+            .map(|(key, value)| format!(" {key}={}", quote_routeros(&value)))
+            .collect())
+    }
+
+    /// Key field to find the entry with in a script, `None` for entries without any key.
+    /// Entries with only a `.id` fail, the ids assigned by [`Client::add`] only exist in this
+    /// client
+    fn script_id_field<Resource: RouterOsResource>(
+        resource: &Resource,
+    ) -> Result<Option<(&'static FieldDescription, &dyn RosFieldAccessor)>, RosError> {
+        let key = resource.fields().find(|(description, value)| {
+            description.is_id && description.name != ".id" && value.has_value()
+        });
+        match (key, resource.id_field()) {
+            (None, Some((_, id))) => Err(no_script_key(
+                Resource::resource_path(),
+                &id.api_value(&ValueFormat::Api),
+            )),
+            (key, _) => Ok(key),
+        }
     }
 
     fn values_of_resource<Resource: RouterOsResource>(
         &mut self,
-    ) -> &mut Vec<HashMap<&'static str, String>> {
-        let values = match self.model.entry(Resource::resource_path()) {
+    ) -> &mut Vec<HashMap<String, String>> {
+        self.values_of_menu(Resource::resource_path())
+    }
+
+    /// Position of the stored entry with the `.id` `id` and the `find` expression to refer to
+    /// it in a script by a key field other than `.id`
+    fn find_by_id<Resource: RouterOsResource>(
+        &mut self,
+        id: &str,
+    ) -> Result<(Option<usize>, String), RosError> {
+        let key_fields: Vec<&'static str> = Resource::default()
+            .fields()
+            .filter(|(description, _)| description.is_id && description.name != ".id")
+            .map(|(description, _)| description.name)
+            .collect();
+        let values = self.values_of_resource::<Resource>();
+        let position = values
+            .iter()
+            .position(|r| r.get(".id").map(String::as_str) == Some(id));
+        let (key, value) = position
+            .and_then(|position| {
+                key_fields.iter().find_map(|key| {
+                    values[position]
+                        .get(*key)
+                        .filter(|value| !value.is_empty())
+                        .map(|value| (*key, value.as_str()))
+                })
+            })
+            .ok_or_else(|| no_script_key(Resource::resource_path(), id))?;
+        Ok((
+            position,
+            format!("[ find where {key}={} ]", quote_routeros(value)),
+        ))
+    }
+
+    fn values_of_menu(&mut self, path: &str) -> &mut Vec<HashMap<String, String>> {
+        let values = match self.model.entry(path.to_owned()) {
             Entry::Occupied(value) => value.into_mut(),
             Entry::Vacant(v) => v.insert(Vec::new()),
         };
         values
     }
 
-    fn write_resource<Resource>(resource: Resource, found_ref: &mut HashMap<&str, String>)
+    fn write_resource<Resource>(resource: Resource, found_ref: &mut HashMap<String, String>)
     where
         Resource: RouterOsResource,
     {
@@ -73,9 +168,45 @@ impl ConfigClient {
             .fields()
             .map(|(description, field)| (description.name, field.api_value(&ValueFormat::Cli)))
         {
-            found_ref.insert(key, value);
+            found_ref.insert(key.to_owned(), value);
+        }
+        for (key, value) in resource
+            .extra_attributes()
+            .into_iter()
+            .flat_map(|e| e.iter())
+        {
+            found_ref.insert(key.to_owned(), value.to_owned());
         }
     }
+
+    fn write_dynamic_resource(resource: &DynamicResource, found_ref: &mut HashMap<String, String>) {
+        for (key, field) in resource.fields() {
+            found_ref.insert(key.to_owned(), field.api_value(&ValueFormat::Cli));
+        }
+    }
+
+    /// Same as [`ConfigClient::script_id_field`] for dynamic menus
+    fn dynamic_script_id_field(
+        resource: &DynamicResource,
+    ) -> Result<Option<(&str, &dyn RosFieldAccessor)>, RosError> {
+        let key = resource.fields().find(|(name, value)| {
+            *name != ".id" && resource.menu().is_key(name) && value.has_value()
+        });
+        match (key, resource.id_field()) {
+            (None, Some((_, id))) => Err(no_script_key(
+                resource.menu().path(),
+                &id.api_value(&ValueFormat::Api),
+            )),
+            (key, _) => Ok(key),
+        }
+    }
+}
+
+/// Error for entries a script cannot find, their `.id` does not exist on the router
+fn no_script_key(path: &str, id: &str) -> RosError {
+    RosError::SimpleMessage(format!(
+        "Entry {id} of {path} has no key besides .id to refer to it in a script"
+    ))
 }
 
 fn quote_routeros(value: &str) -> String {
@@ -94,9 +225,9 @@ fn quote_routeros(value: &str) -> String {
     ret
 }
 
-impl ToString for ConfigClient {
-    fn to_string(&self) -> String {
-        self.output.clone()
+impl Display for ConfigClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.output)
     }
 }
 
@@ -106,13 +237,20 @@ impl Client for ConfigClient {
     where
         Resource: RouterOsResource,
     {
+        let parser = self.parser.clone();
+        let lenient = parser.mode == ParseMode::Lenient;
         let values = self.values_of_resource::<Resource>();
         let mut stored_data = Vec::new();
         for record in values {
             let mut entry = Resource::default();
-            for (description, field) in entry.fields_mut() {
-                if let Some(value) = record.get(description.name) {
-                    field.set_from_api(value)?;
+            for (key, value) in record.iter() {
+                // strict mode ignores attributes stored through dynamic menus
+                if lenient
+                    || entry
+                        .fields()
+                        .any(|(description, _)| description.name == key)
+                {
+                    parser.write(&mut entry, key, value)?;
                 }
             }
             stored_data.push(entry);
@@ -124,7 +262,7 @@ impl Client for ConfigClient {
     where
         Resource: RouterOsListResource,
     {
-        if let Some((description, field)) = resource.id_field() {
+        if let Some((description, field)) = Self::script_id_field(&resource)? {
             if resource.is_modified() {
                 let key = description.name;
                 let api_value = field.api_value(&ValueFormat::Cli);
@@ -134,11 +272,10 @@ impl Client for ConfigClient {
                         .unwrap_or_default()
                         .as_str(),
                 );
+                let fields = self.modified_fields(&resource)?;
                 self.ensure_context(Resource::resource_path());
                 self.output
-                    .push_str(&format!("set [ find where {key}={value} ] "));
-                self.append_modified_fields(&resource);
-                self.output.push('\n');
+                    .push_str(&format!("set [ find where {key}={value} ] {fields}\n"));
 
                 let values = self.values_of_resource::<Resource>();
                 if let Some(found_ref) = values
@@ -156,10 +293,9 @@ impl Client for ConfigClient {
     where
         Resource: RouterOsSingleResource,
     {
+        let fields = self.modified_fields(&resource)?;
         self.ensure_context(Resource::resource_path());
-        self.output.push_str(&format!("set"));
-        self.append_modified_fields(&resource);
-        self.output.push('\n');
+        self.output.push_str(&format!("set{fields}\n"));
 
         let values = self.values_of_resource::<Resource>();
         if values.is_empty() {
@@ -170,20 +306,75 @@ impl Client for ConfigClient {
         //ready(Ok(())).await
     }
 
-    async fn add<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    async fn add_before<Resource>(
+        &mut self,
+        resource: &mut Resource,
+        before: Option<&str>,
+    ) -> Result<Option<String>, RosError>
     where
-        Resource: RouterOsResource,
+        Resource: RouterOsListResource,
     {
-        if resource.is_modified() {
+        if !resource.is_modified() {
+            return Ok(None);
+        }
+        let fields = self.modified_fields(resource)?;
+        let before = before
+            .map(|before| self.find_by_id::<Resource>(before))
+            .transpose()?;
+        self.ensure_context(Resource::resource_path());
+        self.output.push_str(&format!("add{fields}"));
+        if let Some((_, find)) = &before {
+            self.output.push_str(&format!(" place-before={find}"));
+        }
+        self.output.push('\n');
+
+        self.last_id += 1;
+        let id = format!("*{:X}", self.last_id);
+        resource.set_created_id(&id)?;
+        let values = self.values_of_resource::<Resource>();
+        let mut data = HashMap::new();
+        Self::write_resource(resource.clone(), &mut data);
+        match before {
+            Some((Some(position), _)) => values.insert(position, data),
+            _ => values.push(data),
+        }
+        ready(Ok(Some(id))).await
+    }
+
+    async fn move_before<Resource>(
+        &mut self,
+        resource: Resource,
+        before: Option<&str>,
+    ) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        if let Some((description, field)) = Self::script_id_field(&resource)? {
+            let key = description.name;
+            let api_value = field.api_value(&ValueFormat::Cli);
+            let before = before
+                .map(|before| self.find_by_id::<Resource>(before))
+                .transpose()?;
             self.ensure_context(Resource::resource_path());
-            self.output.push_str("add");
-            self.append_modified_fields(&resource);
-            self.output.push_str("\n");
+            self.output.push_str(&format!(
+                "move [ find where {key}={} ]",
+                quote_routeros(&api_value)
+            ));
+            if let Some((_, find)) = &before {
+                self.output.push_str(&format!(" destination={find}"));
+            }
+            self.output.push('\n');
 
             let values = self.values_of_resource::<Resource>();
-            let mut data = HashMap::new();
-            Self::write_resource(resource, &mut data);
-            values.push(data);
+            if let Some(position) = values.iter().position(|r| r.get(key) == Some(&api_value)) {
+                let entry = values.remove(position);
+                let destination = match before {
+                    Some((Some(destination), _)) if destination > position => destination - 1,
+                    Some((Some(destination), _)) => destination,
+                    _ => values.len(),
+                };
+                values.insert(destination, entry);
+            }
         }
         ready(Ok(())).await
     }
@@ -192,16 +383,124 @@ impl Client for ConfigClient {
     where
         Resource: RouterOsResource,
     {
-        if let Some((description, field)) = resource.id_field() {
+        if let Some((description, field)) = Self::script_id_field(&resource)? {
             let key = description.name;
-            let value = quote_routeros(&field.api_value(&ValueFormat::Cli));
+            let api_value = field.api_value(&ValueFormat::Cli);
+            let value = quote_routeros(&api_value);
             self.ensure_context(Resource::resource_path());
             self.output
-                .push_str(&format!("remove [find where {key}={value}]\n"));
+                .push_str(&format!("remove [ find where {key}={value} ]\n"));
 
             let values = self.values_of_resource::<Resource>();
-            values.retain(|r| Some(&value) != r.get(description.name));
+            values.retain(|r| Some(&api_value) != r.get(description.name));
         }
         ready(Ok(())).await
     }
+
+    async fn list_dynamic(&mut self, menu: &DynamicMenu) -> Result<Vec<DynamicResource>, RosError> {
+        let mut stored_data = Vec::new();
+        for record in self.values_of_menu(menu.path()).iter() {
+            let mut entry = menu.create();
+            for (key, value) in record {
+                entry.set_from_api(key, value)?;
+            }
+            stored_data.push(entry);
+        }
+        Ok(stored_data)
+    }
+
+    async fn update_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        if !resource.is_modified() {
+            return Ok(());
+        }
+        let key = Self::dynamic_script_id_field(&resource)?;
+        self.ensure_context(resource.menu().path());
+        let found = match key {
+            Some((key, field)) => {
+                let value = field.original_value(&ValueFormat::Cli).unwrap_or_default();
+                self.output.push_str(&format!(
+                    "set [ find where {key}={} ] ",
+                    quote_routeros(&value)
+                ));
+                Some((key.to_owned(), value))
+            }
+            None => {
+                self.output.push_str("set");
+                None
+            }
+        };
+        for (key, value) in resource.modified_values(&ValueFormat::Cli) {
+            self.output
+                .push_str(&format!(" {key}={}", quote_routeros(&value)));
+        }
+        self.output.push('\n');
+
+        let values = self.values_of_menu(resource.menu().path());
+        let found_ref = match found {
+            Some((key, value)) => values.iter_mut().find(|r| r.get(&key) == Some(&value)),
+            None => {
+                if values.is_empty() {
+                    values.push(HashMap::new());
+                }
+                values.first_mut()
+            }
+        };
+        if let Some(found_ref) = found_ref {
+            Self::write_dynamic_resource(&resource, found_ref);
+        }
+        Ok(())
+    }
+
+    async fn add_dynamic(
+        &mut self,
+        resource: &mut DynamicResource,
+    ) -> Result<Option<String>, RosError> {
+        if !resource.is_modified() {
+            return Ok(None);
+        }
+        self.ensure_context(resource.menu().path());
+        self.output.push_str("add");
+        for (key, value) in resource.modified_values(&ValueFormat::Cli) {
+            self.output
+                .push_str(&format!(" {key}={}", quote_routeros(&value)));
+        }
+        self.output.push('\n');
+
+        self.last_id += 1;
+        let id = format!("*{:X}", self.last_id);
+        resource.set_created_id(&id)?;
+        let mut data = HashMap::new();
+        Self::write_dynamic_resource(resource, &mut data);
+        self.values_of_menu(resource.menu().path()).push(data);
+        Ok(Some(id))
+    }
+
+    async fn delete_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        if let Some((key, field)) = Self::dynamic_script_id_field(&resource)? {
+            let value = field.api_value(&ValueFormat::Cli);
+            self.ensure_context(resource.menu().path());
+            self.output.push_str(&format!(
+                "remove [ find where {key}={} ]\n",
+                quote_routeros(&value)
+            ));
+            self.values_of_menu(resource.menu().path())
+                .retain(|r| r.get(key) != Some(&value));
+        }
+        Ok(())
+    }
+
+    async fn execute(&mut self, command: &Command) -> Result<CommandOutput, RosError> {
+        self.ensure_context(command.menu());
+        self.output.push_str(command.name());
+        for (key, value) in command.arguments(&ValueFormat::Cli) {
+            self.output
+                .push_str(&format!(" {key}={}", quote_routeros(value)));
+        }
+        self.output.push('\n');
+        Ok(CommandOutput::default())
+    }
+
+    fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
 }
@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use crate::client::command::Command;
+use crate::client::config::ConfigClient;
+use crate::client::version::UnsupportedFields;
+use crate::client::{Client, ResourceAccess};
+use crate::generated::interface::bridge::Bridge;
+use crate::generated::ip::address::Address;
+use crate::generated::ip::route::Route;
+use crate::model::dynamic::DynamicMenu;
+use crate::model::ros_value::Auto;
+use crate::model::version::RosVersion;
+use crate::model::{RouterOsResource, ValueFormat};
+use crate::RosError;
+
+fn id_of(bridge: &Bridge) -> Option<String> {
+    bridge
+        .id_field()
+        .map(|(_, field)| field.api_value(&ValueFormat::Api))
+}
+
+#[tokio::test]
+async fn added_entries_get_an_id() {
+    let mut client = ConfigClient::new();
+    let mut bridge = Bridge::default();
+    bridge.name.set("bridge");
+    let id = client.add(&mut bridge).await.unwrap();
+    assert_eq!(id.as_deref(), Some("*1"));
+    assert_eq!(id_of(&bridge).as_deref(), Some("*1"));
+    assert!(client.add(&mut Bridge::default()).await.unwrap().is_none());
+
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    let bridge = bridges.iter_mut().next().unwrap();
+    assert_eq!(id_of(bridge).as_deref(), Some("*1"));
+    bridge.mtu.set(Auto::Value(1500));
+    bridges.commit(&mut client).await.unwrap();
+    assert_eq!(
+        client.dump_cmd(),
+        "/interface bridge\nadd name=\"bridge\"\nset [ find where name=\"bridge\" ]  mtu=\"1500\"\n"
+    );
+}
+
+#[tokio::test]
+async fn dynamic_menus_produce_script_lines() {
+    let menu = DynamicMenu::new("ip/firewall/raw").key_field("comment");
+    let mut client = ConfigClient::new();
+    let mut rule = menu.create();
+    rule.set("chain", "prerouting");
+    rule.set("action", "notrack");
+    rule.set("comment", "no conntrack");
+    assert_eq!(
+        client.add_dynamic(&mut rule).await.unwrap().as_deref(),
+        Some("*1")
+    );
+
+    let mut rules = client.list_dynamic(&menu).await.unwrap();
+    assert_eq!(rules.len(), 1);
+    let mut rule = rules.remove(0);
+    rule.set("disabled", "yes");
+    client.update_dynamic(rule.clone()).await.unwrap();
+    client.delete_dynamic(rule).await.unwrap();
+    assert!(client.list_dynamic(&menu).await.unwrap().is_empty());
+    assert_eq!(
+        client.dump_cmd(),
+        "/ip firewall raw\n\
+         add chain=\"prerouting\" action=\"notrack\" comment=\"no conntrack\"\n\
+         set [ find where comment=\"no conntrack\" ]  disabled=\"yes\"\n\
+         remove [ find where comment=\"no conntrack\" ]\n"
+    );
+}
+
+#[tokio::test]
+async fn dynamic_entries_with_only_an_id_fail() {
+    let menu = DynamicMenu::new("ip/firewall/filter");
+    let mut client = ConfigClient::new();
+    let mut rule = menu.create();
+    rule.set("chain", "input");
+    client.add_dynamic(&mut rule).await.unwrap();
+
+    let mut rule = client.list_dynamic(&menu).await.unwrap().remove(0);
+    rule.set("disabled", "yes");
+    let error = client.update_dynamic(rule.clone()).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Entry *1 of ip/firewall/filter has no key besides .id to refer to it in a script"
+    );
+    assert!(client.delete_dynamic(rule).await.is_err());
+    assert_eq!(
+        client.dump_cmd(),
+        "/ip firewall filter\nadd chain=\"input\"\n"
+    );
+}
+
+#[tokio::test]
+async fn commands_are_written_in_their_menu() {
+    let mut client = ConfigClient::new();
+    let mut bridge = Bridge::default();
+    bridge.name.set("bridge");
+    client.add(&mut bridge).await.unwrap();
+    let reset = Command::of::<Bridge>("reset-counters").argument("numbers", String::from("bridge"));
+    client.execute(&reset).await.unwrap();
+    let wait = Duration::from_secs(5);
+    client
+        .execute(&Command::new("system", "reboot"))
+        .await
+        .unwrap();
+    client
+        .execute(&Command::new("/system/script/", "run").argument("number", String::from("x")))
+        .await
+        .unwrap();
+    client
+        .execute(&Command::new("", "delay").argument("delay-time", wait))
+        .await
+        .unwrap();
+    assert_eq!(
+        client.dump_cmd(),
+        "/interface bridge\n\
+         add name=\"bridge\"\n\
+         reset-counters numbers=\"bridge\"\n\
+         /system\n\
+         reboot\n\
+         /system script\n\
+         run number=\"x\"\n\
+         /\n\
+         delay delay-time=\"5s\"\n"
+    );
+}
+
+#[tokio::test]
+async fn fields_of_other_versions_are_rejected_or_skipped() {
+    let mut route = Route::default();
+    route
+        .dst_address
+        .set("10.1.0.0/16".parse::<ipnet::IpNet>().unwrap());
+    route.routing_table.set("vpn");
+
+    let mut client = ConfigClient::new().with_version(RosVersion::new(6, 49, 10));
+    let error = client.add(&mut route.clone()).await.unwrap_err();
+    assert!(matches!(
+        error,
+        RosError::UnsupportedField {
+            structure: "ip/route",
+            field_name: "routing-table",
+            ..
+        }
+    ));
+    assert_eq!(
+        error.to_string(),
+        "Field routing-table of ip/route is not supported by RouterOS 6.49.10"
+    );
+    assert_eq!(client.dump_cmd(), "");
+
+    let mut client = client.unsupported_fields(UnsupportedFields::Skip);
+    client.add(&mut route.clone()).await.unwrap();
+    assert_eq!(
+        client.dump_cmd(),
+        "/ip route\nadd dst-address=\"10.1.0.0/16\"\n"
+    );
+
+    let mut client = ConfigClient::new().with_version(RosVersion::new(7, 12, 1));
+    client.add(&mut route).await.unwrap();
+    assert_eq!(
+        client.dump_cmd(),
+        "/ip route\nadd dst-address=\"10.1.0.0/16\" routing-table=\"vpn\"\n"
+    );
+}
+
+#[tokio::test]
+async fn entries_with_only_an_id_are_not_referred_to() {
+    let mut client = ConfigClient::new();
+    let mut address = Address::default();
+    address
+        .address
+        .set("10.0.0.1/24".parse::<ipnet::IpNet>().unwrap());
+    client.add(&mut address).await.unwrap();
+    client.dump_cmd();
+
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    for address in addresses.iter_mut() {
+        address.interface.set("ether1");
+    }
+    let error = addresses.commit(&mut client).await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Entry *1 of ip/address has no key besides .id to refer to it in a script"
+    );
+    assert!(client.delete(address).await.is_err());
+    assert_eq!(client.dump_cmd(), "");
+}
@@ -0,0 +1,395 @@
+use std::cmp::Ordering;
+use std::marker::PhantomData;
+use std::ops::Not;
+
+use field_ref::FieldRef;
+
+use crate::model::{RosFieldValue, RosValue, RouterOsResource, ValueFormat};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Single word of a RouterOS query, each one pushes a result onto the query stack
+/// except [`Query::Operations`] which combines the results already on it
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Query {
+    HasValue(String),
+    HasNoValue(String),
+    Equals {
+        key: String,
+        value: String,
+    },
+    Lt {
+        key: String,
+        value: String,
+    },
+    Gt {
+        key: String,
+        value: String,
+    },
+    /// Stack operations of a `?#` word: `|` or, `&` and, `!` not, `.` duplicate top
+    Operations(String),
+}
+
+/// Typed filter for the entries of `Resource`, evaluated by the router where the
+/// client supports it.
+///
+/// Conditions added one after the other must all match, use [`Filter::any_of`], `!` or
+/// the stack operations to combine them differently. A field reference to a field that is
+/// not an api field of `Resource` makes the filter fail when it is used.
+///
+/// ```ignore
+/// let default_route = Filter::new().equals(&field_ref!(Route => dst_address), default_net);
+/// let routes: Vec<Route> = client.list_where(&default_route).await?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Filter<Resource> {
+    queries: Vec<Query>,
+    depth: usize,
+    /// First invalid field reference
+    error: Option<String>,
+    resource: PhantomData<fn() -> Resource>,
+}
+
+impl<Resource: RouterOsResource> Default for Filter<Resource> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Resource: RouterOsResource> Filter<Resource> {
+    pub fn new() -> Filter<Resource> {
+        Filter {
+            queries: Vec::new(),
+            depth: 0,
+            error: None,
+            resource: PhantomData,
+        }
+    }
+
+    pub fn has_value<V>(self, field: &FieldRef<Resource, RosFieldValue<V>>) -> Self
+    where
+        V: RosValue<Type = V>,
+    {
+        self.push_field(field, Query::HasValue)
+    }
+    pub fn has_no_value<V>(self, field: &FieldRef<Resource, RosFieldValue<V>>) -> Self
+    where
+        V: RosValue<Type = V>,
+    {
+        self.push_field(field, Query::HasNoValue)
+    }
+    pub fn equals<V, IV>(self, field: &FieldRef<Resource, RosFieldValue<V>>, value: IV) -> Self
+    where
+        V: RosValue<Type = V>,
+        IV: Into<V>,
+    {
+        let value = value.into().to_api(&ValueFormat::Api);
+        self.push_field(field, |key| Query::Equals { key, value })
+    }
+    pub fn less_than<V, IV>(self, field: &FieldRef<Resource, RosFieldValue<V>>, value: IV) -> Self
+    where
+        V: RosValue<Type = V>,
+        IV: Into<V>,
+    {
+        let value = value.into().to_api(&ValueFormat::Api);
+        self.push_field(field, |key| Query::Lt { key, value })
+    }
+    pub fn greater_than<V, IV>(
+        self,
+        field: &FieldRef<Resource, RosFieldValue<V>>,
+        value: IV,
+    ) -> Self
+    where
+        V: RosValue<Type = V>,
+        IV: Into<V>,
+    {
+        let value = value.into().to_api(&ValueFormat::Api);
+        self.push_field(field, |key| Query::Gt { key, value })
+    }
+
+    /// Replaces the two topmost results by their disjunction (`?#|`)
+    pub fn or(self) -> Self {
+        self.operation('|')
+    }
+    /// Replaces the two topmost results by their conjunction (`?#&`)
+    pub fn and(self) -> Self {
+        self.operation('&')
+    }
+
+    /// Matches entries that match at least one of `filters`, an empty filter matches everything
+    pub fn any_of<I>(filters: I) -> Self
+    where
+        I: IntoIterator<Item = Filter<Resource>>,
+    {
+        let mut ret = Self::new();
+        let mut count = 0;
+        let mut matches_all = false;
+        for filter in filters {
+            // an invalid field reference leaves no condition, but the error has to stay
+            if filter.depth == 0 && filter.error.is_none() {
+                matches_all = true;
+            } else if filter.depth == 0 {
+                ret.error = ret.error.or(filter.error);
+            } else {
+                ret = ret.append(filter.reduced());
+                count += 1;
+            }
+        }
+        if matches_all {
+            return Filter {
+                error: ret.error,
+                ..Self::new()
+            };
+        }
+        (1..count).fold(ret, |ret, _| ret.or())
+    }
+    /// Matches entries that match all of `filters`
+    pub fn all_of<I>(filters: I) -> Self
+    where
+        I: IntoIterator<Item = Filter<Resource>>,
+    {
+        filters
+            .into_iter()
+            .fold(Self::new(), |ret, filter| ret.append(filter))
+    }
+
+    /// Query words of the filter, fails if a field reference was invalid
+    pub fn queries(&self) -> Result<&[Query], RosError> {
+        self.check()?;
+        Ok(&self.queries)
+    }
+    /// Fails if a field reference of the filter is not an api field of `Resource`
+    pub fn check(&self) -> Result<(), RosError> {
+        match &self.error {
+            Some(error) => Err(RosError::SimpleMessage(error.clone())),
+            None => Ok(()),
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queries.is_empty()
+    }
+
+    /// Evaluates the filter locally the same way RouterOS evaluates the query words
+    pub fn matches(&self, resource: &Resource) -> bool {
+        let value_of = |key: &str| {
+            resource
+                .fields()
+                .find(|(description, _)| description.name == key)
+                .filter(|(_, field)| field.has_value())
+                .map(|(_, field)| field.api_value(&ValueFormat::Api))
+        };
+        evaluate(&self.queries, value_of)
+    }
+
+    fn push(mut self, query: Query) -> Self {
+        self.queries.push(query);
+        self.depth += 1;
+        self
+    }
+    fn push_field<V, F>(mut self, field: &FieldRef<Resource, RosFieldValue<V>>, query: F) -> Self
+    where
+        V: RosValue<Type = V>,
+        F: FnOnce(String) -> Query,
+    {
+        match field_name(field) {
+            Ok(name) => self.push(query(name.to_owned())),
+            Err(error) => {
+                self.error.get_or_insert(error.to_string());
+                self
+            }
+        }
+    }
+    fn operation(mut self, operation: char) -> Self {
+        match operation {
+            '|' | '&' => self.depth = self.depth.saturating_sub(1),
+            '.' => self.depth += 1,
+            _ => {}
+        }
+        if let Some(Query::Operations(operations)) = self.queries.last_mut() {
+            operations.push(operation);
+        } else {
+            self.queries.push(Query::Operations(operation.to_string()));
+        }
+        self
+    }
+    fn append(mut self, other: Filter<Resource>) -> Self {
+        self.queries.extend(other.queries);
+        self.depth += other.depth;
+        self.error = self.error.or(other.error);
+        self
+    }
+    /// Combines all results of the filter into a single one
+    fn reduced(self) -> Self {
+        let depth = self.depth;
+        (1..depth).fold(self, |ret, _| ret.and())
+    }
+}
+
+/// Attributes to read from the router (`.proplist`), the other fields of the listed
+/// entries stay empty.
+///
+/// The id field is always part of the projection, so the entries can still be updated.
+#[derive(Debug, Clone)]
+pub struct Projection<Resource> {
+    names: Vec<&'static str>,
+    /// First invalid field reference
+    error: Option<String>,
+    resource: PhantomData<fn() -> Resource>,
+}
+
+impl<Resource: RouterOsResource> Default for Projection<Resource> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Resource: RouterOsResource> Projection<Resource> {
+    pub fn new() -> Projection<Resource> {
+        let names = Resource::default()
+            .fields()
+            .filter(|(description, _)| description.is_id)
+            .map(|(description, _)| description.name)
+            .collect();
+        Projection {
+            names,
+            error: None,
+            resource: PhantomData,
+        }
+    }
+
+    pub fn field<V>(mut self, field: &FieldRef<Resource, RosFieldValue<V>>) -> Self
+    where
+        V: RosValue<Type = V>,
+    {
+        match field_name(field) {
+            Ok(name) if !self.names.contains(&name) => self.names.push(name),
+            Ok(_) => {}
+            Err(error) => {
+                self.error.get_or_insert(error.to_string());
+            }
+        }
+        self
+    }
+    /// Fails if a field reference of the projection is not an api field of `Resource`
+    pub fn check(&self) -> Result<(), RosError> {
+        match &self.error {
+            Some(error) => Err(RosError::SimpleMessage(error.clone())),
+            None => Ok(()),
+        }
+    }
+
+    pub fn names(&self) -> &[&'static str] {
+        &self.names
+    }
+    /// Value of `.proplist`, fails if a field reference was invalid
+    pub fn proplist(&self) -> Result<String, RosError> {
+        self.check()?;
+        Ok(self.names.join(","))
+    }
+
+    /// Copy of `resource` with only the selected fields, for clients that cannot
+    /// select attributes on the router
+    pub fn apply(&self, resource: &Resource) -> Result<Resource, RosError> {
+        self.check()?;
+        let mut ret = Resource::default();
+        for (description, field) in ret.fields_mut() {
+            if !self.names.contains(&description.name) {
+                continue;
+            }
+            if let Some((_, source)) = resource
+                .fields()
+                .find(|(source, _)| source.name == description.name)
+            {
+                field.set_from_api(&source.api_value(&ValueFormat::Api))?;
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// Matches the entries the filter does not match (`?#!` on the combined results)
+impl<Resource: RouterOsResource> Not for Filter<Resource> {
+    type Output = Filter<Resource>;
+
+    fn not(self) -> Self::Output {
+        self.reduced().operation('!')
+    }
+}
+
+/// Evaluates query words against an entry the way RouterOS does, `value_of` returns the
+/// api value of an attribute or `None` if the entry has no value for it
+pub(crate) fn evaluate<F>(queries: &[Query], value_of: F) -> bool
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut stack: Vec<bool> = Vec::new();
+    for query in queries {
+        match query {
+            Query::HasValue(key) => stack.push(value_of(key).is_some()),
+            Query::HasNoValue(key) => stack.push(value_of(key).is_none()),
+            Query::Equals { key, value } => stack.push(value_of(key).unwrap_or_default() == *value),
+            Query::Lt { key, value } => {
+                stack.push(value_of(key).is_some_and(|v| compare(&v, value) == Ordering::Less))
+            }
+            Query::Gt { key, value } => {
+                stack.push(value_of(key).is_some_and(|v| compare(&v, value) == Ordering::Greater))
+            }
+            Query::Operations(operations) => {
+                for operation in operations.chars() {
+                    match operation {
+                        '|' | '&' => {
+                            let right = stack.pop().unwrap_or(false);
+                            let left = stack.pop().unwrap_or(false);
+                            stack.push(if operation == '|' {
+                                left || right
+                            } else {
+                                left && right
+                            });
+                        }
+                        '!' => {
+                            let top = stack.pop().unwrap_or(false);
+                            stack.push(!top);
+                        }
+                        '.' => {
+                            let top = stack.last().copied().unwrap_or(false);
+                            stack.push(top);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+    stack.into_iter().all(|result| result)
+}
+
+/// Compares numbers by value and everything else as text like RouterOS does
+fn compare(left: &str, right: &str) -> Ordering {
+    match (left.parse::<i128>(), right.parse::<i128>()) {
+        (Ok(left), Ok(right)) => left.cmp(&right),
+        _ => left.cmp(right),
+    }
+}
+
+fn field_name<Resource, V>(
+    field: &FieldRef<Resource, RosFieldValue<V>>,
+) -> Result<&'static str, RosError>
+where
+    Resource: RouterOsResource,
+    V: RosValue<Type = V>,
+{
+    let resource = Resource::default();
+    let target = field.get(&resource) as *const RosFieldValue<V> as *const ();
+    let name = resource
+        .fields()
+        .find(|(_, accessor)| std::ptr::eq(*accessor as *const _ as *const (), target))
+        .map(|(description, _)| description.name);
+    name.ok_or_else(|| {
+        RosError::SimpleMessage(format!(
+            "field reference is not an api field of {}",
+            Resource::resource_path()
+        ))
+    })
+}
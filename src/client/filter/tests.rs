@@ -0,0 +1,147 @@
+use ipnet::IpNet;
+
+use crate::client::filter::{Filter, Projection, Query};
+use crate::field_ref;
+use crate::generated::ip::route::Route;
+use crate::model::{
+    FieldDescription, RosFieldAccessor, RosFieldValue, RouterOsApiFieldAccess, RouterOsResource,
+};
+
+fn route(attributes: &[(&str, &str)]) -> Route {
+    let mut route = Route::default();
+    for (key, value) in attributes {
+        let (_, field) = route
+            .fields_mut()
+            .find(|(description, _)| description.name == *key)
+            .unwrap();
+        field.set_from_api(value).unwrap();
+    }
+    route
+}
+
+#[test]
+fn compiles_to_query_words() {
+    let default_net: IpNet = "0.0.0.0/0".parse().unwrap();
+    let filter = !Filter::new()
+        .equals(&field_ref!(Route => dst_address), default_net)
+        .less_than(&field_ref!(Route => distance), 5)
+        .has_no_value(&field_ref!(Route => routing_table));
+    assert_eq!(
+        filter.queries().unwrap(),
+        &[
+            Query::Equals {
+                key: String::from("dst-address"),
+                value: String::from("0.0.0.0/0")
+            },
+            Query::Lt {
+                key: String::from("distance"),
+                value: String::from("5")
+            },
+            Query::HasNoValue(String::from("routing-table")),
+            Query::Operations(String::from("&&!")),
+        ]
+    );
+}
+
+#[test]
+fn any_of_reduces_each_filter() {
+    let filter = Filter::any_of([
+        Filter::new()
+            .equals(&field_ref!(Route => dynamic), true)
+            .equals(&field_ref!(Route => active), true),
+        Filter::new().greater_than(&field_ref!(Route => distance), 10),
+    ]);
+    assert_eq!(
+        filter.queries().unwrap().last(),
+        Some(&Query::Operations(String::from("|")))
+    );
+    assert_eq!(
+        filter.queries().unwrap()[2],
+        Query::Operations(String::from("&"))
+    );
+
+    assert!(filter.matches(&route(&[("dynamic", "true"), ("active", "true")])));
+    assert!(filter.matches(&route(&[("distance", "20")])));
+    assert!(!filter.matches(&route(&[("dynamic", "true"), ("distance", "9")])));
+    assert!(Filter::<Route>::any_of([]).matches(&route(&[])));
+}
+
+#[test]
+fn matches_like_the_router() {
+    let filter = Filter::new()
+        .has_value(&field_ref!(Route => gateway))
+        .less_than(&field_ref!(Route => distance), 10);
+    assert!(filter.matches(&route(&[("gateway", "10.0.0.1"), ("distance", "9")])));
+    assert!(!filter.matches(&route(&[("gateway", "10.0.0.1"), ("distance", "10")])));
+    assert!(!filter.matches(&route(&[("distance", "1")])));
+    assert!(Filter::new().matches(&route(&[])));
+    assert!((!filter).matches(&route(&[("distance", "1")])));
+}
+
+#[test]
+fn projection_keeps_id_and_selected_fields() {
+    let projection = Projection::new()
+        .field(&field_ref!(Route => gateway))
+        .field(&field_ref!(Route => distance))
+        .field(&field_ref!(Route => gateway));
+    assert_eq!(projection.proplist().unwrap(), ".id,gateway,distance");
+
+    let route = projection
+        .apply(&route(&[
+            (".id", "*2"),
+            ("gateway", "10.0.0.1"),
+            ("distance", "1"),
+            ("comment", "uplink"),
+        ]))
+        .unwrap();
+    assert_eq!(route.distance.get(), &Some(1));
+    assert_eq!(route.comment.get(), &None);
+    assert!(!route.is_modified());
+}
+
+/// Resource with a field the api does not know
+#[derive(Debug, Default, Clone)]
+struct Hidden {
+    local: RosFieldValue<String>,
+}
+
+impl RouterOsApiFieldAccess for Hidden {
+    fn fields_mut(
+        &mut self,
+    ) -> Box<dyn Iterator<Item = (&'static FieldDescription, &mut dyn RosFieldAccessor)> + '_> {
+        Box::new(std::iter::empty())
+    }
+    fn fields(
+        &self,
+    ) -> Box<dyn Iterator<Item = (&'static FieldDescription, &dyn RosFieldAccessor)> + '_> {
+        Box::new(std::iter::empty())
+    }
+}
+
+impl RouterOsResource for Hidden {
+    fn resource_path() -> &'static str {
+        "hidden"
+    }
+}
+
+#[test]
+fn fields_without_api_name_fail_on_use() {
+    let filter = Filter::new().equals(&field_ref!(Hidden => local), "value");
+    let error = filter.queries().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "field reference is not an api field of hidden"
+    );
+    assert!(Filter::all_of([Filter::new(), filter.clone()])
+        .check()
+        .is_err());
+    let valid = Filter::new().push(Query::HasValue(String::from("local")));
+    assert!(Filter::any_of([Filter::new(), filter.clone()])
+        .queries()
+        .is_err());
+    assert!(Filter::any_of([valid, filter]).queries().is_err());
+
+    let projection = Projection::new().field(&field_ref!(Hidden => local));
+    assert!(projection.proplist().is_err());
+    assert!(projection.apply(&Hidden::default()).is_err());
+}
@@ -0,0 +1,398 @@
+//! Undo log for [`crate::client::ResourceAccess::commit_transactional`].
+//!
+//! [`JournalingClient`] passes every call to the wrapped client and records the inverse of
+//! each change the router accepted: deleted entries are added again with their original
+//! attributes, modified entries get their original values back and added entries are
+//! removed. The inverse operations are sent through the dynamic menu methods, so the
+//! journal does not need to know the resource types.
+//!
+//! Changes to dynamic menus and commands are passed through without being recorded.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use async_trait::async_trait;
+
+use crate::client::command::{Command, CommandOutput};
+use crate::client::filter::{Filter, Projection};
+use crate::client::Client;
+use crate::model::dynamic::{DynamicMenu, DynamicResource};
+use crate::model::extra::ParseWarning;
+use crate::model::{RouterOsListResource, RouterOsResource, RouterOsSingleResource, ValueFormat};
+use crate::RosError;
+
+/// Inverse of a change sent to the router
+#[derive(Debug, Clone)]
+pub enum Compensation {
+    /// Deleted entry `id` added again, in ordered menus in front of `before`
+    Readd {
+        entry: DynamicResource,
+        id: Option<String>,
+        before: Option<String>,
+    },
+    /// Modified entry set back to its original values
+    Restore(DynamicResource),
+    /// Added entry removed again
+    Remove(DynamicResource),
+    /// Moved entry moved back in front of `before`, to the end if `None`
+    MoveBack {
+        path: String,
+        id: String,
+        before: Option<String>,
+    },
+}
+
+/// Outcome of undoing a failed commit
+#[derive(Debug, Default)]
+pub struct CompensationReport {
+    /// Inverse operations the router accepted, in the order they were sent
+    pub compensated: Vec<Compensation>,
+    /// Inverse operations that failed, the router keeps these changes
+    pub failed: Vec<(Compensation, RosError)>,
+}
+
+/// Client that records the inverse of every change it sends, see the module documentation
+pub struct JournalingClient<'c, C: Client> {
+    client: &'c mut C,
+    journal: Vec<Compensation>,
+}
+
+fn menu_of<R: RouterOsResource>() -> DynamicMenu {
+    R::default()
+        .fields()
+        .filter(|(description, _)| description.is_id)
+        .fold(
+            DynamicMenu::new(R::resource_path()),
+            |menu, (description, _)| menu.key_field(description.name),
+        )
+}
+
+/// Entry as it is on the router after `resource` was written, with the original values
+/// of the modified fields set as new values
+fn restore_of<R: RouterOsResource>(resource: &R) -> Result<DynamicResource, RosError> {
+    let mut restore = menu_of::<R>().create();
+    for (description, field) in resource.fields() {
+        let modified = field.modified_value(&ValueFormat::Api).is_some();
+        if !(modified || description.is_id && field.has_value()) {
+            continue;
+        }
+        restore.set_from_api(description.name, &field.api_value(&ValueFormat::Api))?;
+        if modified {
+            match field.original_value(&ValueFormat::Api) {
+                Some(original) => restore.set(description.name, original),
+                None => restore.clear(description.name),
+            }
+        }
+    }
+    Ok(restore)
+}
+
+/// New entry with the attributes `resource` had on the router, including the ones lenient
+/// parsing kept in its extra attributes
+fn readd_of<R: RouterOsResource>(resource: &R, before: Option<String>) -> Compensation {
+    let mut readd = menu_of::<R>().create();
+    for (description, field) in resource.fields() {
+        if description.is_read_only || description.name == ".id" {
+            continue;
+        }
+        if let Some(original) = field.original_value(&ValueFormat::Api) {
+            readd.set(description.name, original);
+        }
+    }
+    if let Some(extra) = resource.extra_attributes() {
+        for (name, original) in extra.original_values() {
+            readd.set(name, original);
+        }
+    }
+    Compensation::Readd {
+        entry: readd,
+        id: id_of(resource),
+        before,
+    }
+}
+
+/// Reference to the entry `id` and its key fields, to remove it again
+fn remove_of<R: RouterOsResource>(resource: &R, id: &str) -> Result<DynamicResource, RosError> {
+    let mut remove = menu_of::<R>().create();
+    remove.set_from_api(".id", id)?;
+    for (description, field) in resource.fields() {
+        if description.is_id && description.name != ".id" && field.has_value() {
+            remove.set_from_api(description.name, &field.api_value(&ValueFormat::Api))?;
+        }
+    }
+    Ok(remove)
+}
+
+fn id_of<R: RouterOsResource>(resource: &R) -> Option<String> {
+    resource
+        .fields()
+        .find(|(description, field)| description.name == ".id" && field.has_value())
+        .map(|(_, field)| field.api_value(&ValueFormat::Api))
+}
+
+impl<'c, C: Client> JournalingClient<'c, C> {
+    pub fn new(client: &'c mut C) -> JournalingClient<'c, C> {
+        JournalingClient {
+            client,
+            journal: Vec::new(),
+        }
+    }
+    /// Inverse operations recorded so far, in the order the changes were sent
+    pub fn journal(&self) -> &[Compensation] {
+        &self.journal
+    }
+    /// Successor of the entry `id` in the menu of `Resource`
+    async fn successor_of<Resource>(&mut self, id: &str) -> Result<Option<String>, RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let entries: Vec<Resource> = self.client.list().await?;
+        Ok(entries
+            .iter()
+            .skip_while(|entry| id_of(*entry).as_deref() != Some(id))
+            .nth(1)
+            .and_then(id_of))
+    }
+    /// Sends the inverse operations, the last change first, and reports the outcome
+    pub async fn undo(self) -> CompensationReport {
+        let client = self.client;
+        let mut report = CompensationReport::default();
+        // entries added again get new ids, later operations refer to them by the old ones
+        let mut new_ids: HashMap<String, String> = HashMap::new();
+        let current =
+            |new_ids: &HashMap<String, String>, id: &String| new_ids.get(id).unwrap_or(id).clone();
+        for compensation in self.journal.into_iter().rev() {
+            let result = match &compensation {
+                Compensation::Readd { entry, id, before } => {
+                    let mut entry = entry.clone();
+                    if let Some(before) = before {
+                        entry.set("place-before", current(&new_ids, before));
+                    }
+                    client.add_dynamic(&mut entry).await.map(|new_id| {
+                        if let (Some(id), Some(new_id)) = (id, new_id) {
+                            new_ids.insert(id.clone(), new_id);
+                        }
+                    })
+                }
+                Compensation::Restore(restore) => client.update_dynamic(restore.clone()).await,
+                Compensation::Remove(remove) => client.delete_dynamic(remove.clone()).await,
+                Compensation::MoveBack { path, id, before } => {
+                    let mut command = Command::new(path.as_str(), "move")
+                        .argument("numbers", current(&new_ids, id));
+                    if let Some(before) = before {
+                        command = command.argument("destination", current(&new_ids, before));
+                    }
+                    client.execute(&command).await.map(drop)
+                }
+            };
+            match result {
+                Ok(()) => report.compensated.push(compensation),
+                Err(error) => report.failed.push((compensation, error)),
+            }
+        }
+        report
+    }
+}
+
+#[async_trait]
+impl<C: Client> Client for JournalingClient<'_, C> {
+    async fn list<Resource>(&mut self) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.client.list().await
+    }
+
+    async fn list_where<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.client.list_where(filter).await
+    }
+
+    async fn list_projected<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+        projection: &Projection<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.client.list_projected(filter, projection).await
+    }
+
+    async fn update<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let restore = restore_of(&resource)?;
+        let modified = resource.is_modified();
+        self.client.update(resource).await?;
+        if modified {
+            self.journal.push(Compensation::Restore(restore));
+        }
+        Ok(())
+    }
+
+    async fn set<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsSingleResource,
+    {
+        let restore = restore_of(&resource)?;
+        let modified = resource.is_modified();
+        self.client.set(resource).await?;
+        if modified {
+            self.journal.push(Compensation::Restore(restore));
+        }
+        Ok(())
+    }
+
+    async fn add_before<Resource>(
+        &mut self,
+        resource: &mut Resource,
+        before: Option<&str>,
+    ) -> Result<Option<String>, RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let id = self.client.add_before(resource, before).await?;
+        if let Some(id) = &id {
+            self.journal
+                .push(Compensation::Remove(remove_of(resource, id)?));
+        }
+        Ok(id)
+    }
+
+    async fn move_before<Resource>(
+        &mut self,
+        resource: Resource,
+        before: Option<&str>,
+    ) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let id = match id_of(&resource) {
+            Some(id) => id,
+            None => return self.client.move_before(resource, before).await,
+        };
+        let successor = self.successor_of::<Resource>(&id).await?;
+        self.client.move_before(resource, before).await?;
+        self.journal.push(Compensation::MoveBack {
+            path: Resource::resource_path().to_owned(),
+            id,
+            before: successor,
+        });
+        Ok(())
+    }
+
+    async fn delete<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let readd = readd_of(&resource, None);
+        self.client.delete(resource).await?;
+        self.journal.push(readd);
+        Ok(())
+    }
+
+    async fn delete_ordered<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let successor = match id_of(&resource) {
+            Some(id) => self.successor_of::<Resource>(&id).await?,
+            None => None,
+        };
+        let readd = readd_of(&resource, successor);
+        self.client.delete(resource).await?;
+        self.journal.push(readd);
+        Ok(())
+    }
+
+    async fn list_dynamic(&mut self, menu: &DynamicMenu) -> Result<Vec<DynamicResource>, RosError> {
+        self.client.list_dynamic(menu).await
+    }
+
+    async fn update_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        self.client.update_dynamic(resource).await
+    }
+
+    async fn add_dynamic(
+        &mut self,
+        resource: &mut DynamicResource,
+    ) -> Result<Option<String>, RosError> {
+        self.client.add_dynamic(resource).await
+    }
+
+    async fn delete_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        self.client.delete_dynamic(resource).await
+    }
+
+    async fn execute(&mut self, command: &Command) -> Result<CommandOutput, RosError> {
+        self.client.execute(command).await
+    }
+
+    fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.client.take_warnings()
+    }
+}
+
+fn write_fields(f: &mut Formatter<'_>, resource: &DynamicResource) -> std::fmt::Result {
+    for (key, value) in resource.modified_values(&ValueFormat::Api) {
+        write!(f, " {key}={value}")?;
+    }
+    Ok(())
+}
+
+impl Display for Compensation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Compensation::Readd { entry, before, .. } => {
+                write!(f, "add {}", entry.menu().path())?;
+                write_fields(f, entry)?;
+                match before {
+                    Some(before) => write!(f, " before {before}"),
+                    None => Ok(()),
+                }
+            }
+            Compensation::Restore(restore) => {
+                write!(f, "set {}", restore.menu().path())?;
+                if let Some((key, field)) = restore.id_field() {
+                    let value = field.original_value(&ValueFormat::Api).unwrap_or_default();
+                    write!(f, " [{key}={value}]")?;
+                }
+                write_fields(f, restore)
+            }
+            Compensation::Remove(remove) => {
+                write!(f, "remove {}", remove.menu().path())?;
+                if let Some((key, field)) = remove.id_field() {
+                    write!(f, " [{key}={}]", field.api_value(&ValueFormat::Api))?;
+                }
+                Ok(())
+            }
+            Compensation::MoveBack { path, id, before } => {
+                write!(f, "move {path} [.id={id}]")?;
+                match before {
+                    Some(before) => write!(f, " before {before}"),
+                    None => f.write_str(" to the end"),
+                }
+            }
+        }
+    }
+}
+
+impl Display for CompensationReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} changes undone", self.compensated.len())?;
+        if !self.failed.is_empty() {
+            write!(f, ", {} could not be undone:", self.failed.len())?;
+            for (compensation, error) in &self.failed {
+                write!(f, "\n  {compensation}: {error}")?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use async_trait::async_trait;
+
+use crate::client::plan::{ChangePlan, EntryRef, PlannedChange, Removal};
+use crate::client::{referenced_menus, Client, ResourceAccess};
+use crate::model::{RouterOsListResource, ValueFormat};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Entries of a menu whose order matters, like firewall rules or queue trees.
+///
+/// The entries are kept in the desired order. On commit new entries are added with
+/// `place-before` and the fewest entries necessary are moved, the ones that keep their
+/// relative order stay in place.
+#[derive(Debug)]
+pub struct OrderedListAccess<R>
+where
+    R: RouterOsListResource,
+{
+    /// Desired order, with the position at fetch time of entries that exist on the router
+    entries: Vec<(Option<usize>, R)>,
+    removed: Vec<R>,
+}
+
+impl<R: RouterOsListResource> Default for OrderedListAccess<R> {
+    fn default() -> Self {
+        OrderedListAccess {
+            entries: Vec::new(),
+            removed: Vec::new(),
+        }
+    }
+}
+
+impl<R> OrderedListAccess<R>
+where
+    R: RouterOsListResource,
+{
+    pub(crate) fn new(fetched_data: Vec<R>) -> OrderedListAccess<R> {
+        OrderedListAccess {
+            entries: fetched_data
+                .into_iter()
+                .enumerate()
+                .map(|(position, entry)| (Some(position), entry))
+                .collect(),
+            removed: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn get(&self, index: usize) -> Option<&R> {
+        self.entries.get(index).map(|(_, entry)| entry)
+    }
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut R> {
+        self.entries.get_mut(index).map(|(_, entry)| entry)
+    }
+    /// Index of the first entry matching `filter`
+    pub fn position<F>(&self, filter: F) -> Option<usize>
+    where
+        F: Fn(&R) -> bool,
+    {
+        self.entries.iter().position(|(_, entry)| filter(entry))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &R> + '_ {
+        self.entries.iter().map(|(_, entry)| entry)
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut R> + '_ {
+        self.entries.iter_mut().map(|(_, entry)| entry)
+    }
+    pub fn to_be_deleted_iter(&self) -> impl Iterator<Item = &R> + '_ {
+        self.removed.iter()
+    }
+
+    /// Appends a new entry
+    pub fn push(&mut self, entry: R) -> &mut R {
+        self.insert(self.entries.len(), entry)
+    }
+    /// Inserts a new entry in front of the one at `index`
+    pub fn insert(&mut self, index: usize, entry: R) -> &mut R {
+        self.entries.insert(index, (None, entry));
+        &mut self.entries[index].1
+    }
+    /// Removes the entry at `index`, it is deleted on commit if it exists on the router
+    pub fn remove(&mut self, index: usize) -> R {
+        let (position, entry) = self.entries.remove(index);
+        if position.is_some() {
+            self.removed.push(entry.clone());
+        }
+        entry
+    }
+    /// Removes all entries not matching `filter`
+    pub fn retain<F>(&mut self, filter: F)
+    where
+        F: Fn(&R) -> bool,
+    {
+        let (keep, remove): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|(_, entry)| filter(entry));
+        self.entries = keep;
+        self.removed.extend(
+            remove
+                .into_iter()
+                .filter(|(position, _)| position.is_some())
+                .map(|(_, entry)| entry),
+        );
+    }
+    /// Moves the entry at `from` so it ends up at index `to`
+    pub fn move_to(&mut self, from: usize, to: usize) {
+        let entry = self.entries.remove(from);
+        self.entries.insert(to, entry);
+    }
+
+    /// Entries that keep their place, the longest run of existing entries that is still
+    /// in the fetched order
+    fn stable_entries(&self) -> Vec<bool> {
+        let existing: Vec<(usize, usize)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, (position, _))| position.map(|position| (index, position)))
+            .collect();
+        // tails[k]: index into `existing` of the smallest tail of a run of length k + 1
+        let mut tails: Vec<usize> = Vec::new();
+        let mut previous: Vec<Option<usize>> = vec![None; existing.len()];
+        for (current, (_, position)) in existing.iter().enumerate() {
+            let length = tails.partition_point(|tail| existing[*tail].1 < *position);
+            previous[current] = length.checked_sub(1).map(|before| tails[before]);
+            if length == tails.len() {
+                tails.push(current);
+            } else {
+                tails[length] = current;
+            }
+        }
+        let mut ret = vec![false; self.entries.len()];
+        let mut current = tails.last().copied();
+        while let Some(index) = current {
+            ret[existing[index].0] = true;
+            current = previous[index];
+        }
+        ret
+    }
+}
+
+fn id_of<R: RouterOsListResource>(entry: &R) -> Option<String> {
+    entry
+        .fields()
+        .find(|(description, field)| description.name == ".id" && field.has_value())
+        .map(|(_, field)| field.api_value(&ValueFormat::Api))
+}
+
+#[async_trait]
+impl<R: RouterOsListResource> ResourceAccess for OrderedListAccess<R> {
+    async fn commit_remove<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
+    where
+        C: Client + 'a,
+    {
+        for entry in std::mem::take(&mut self.removed) {
+            if !entry.is_dynamic() {
+                client.delete_ordered(entry).await?;
+            }
+        }
+        Ok(())
+    }
+    async fn commit_update<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
+    where
+        C: Client + 'a,
+    {
+        let modified_entries: Vec<R> = self
+            .entries
+            .iter()
+            .filter(|(position, entry)| {
+                position.is_some() && !entry.is_dynamic() && entry.is_modified()
+            })
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        for entry in modified_entries {
+            client.update(entry).await?;
+        }
+        Ok(())
+    }
+    /// Moves existing entries into the desired order and adds the new ones
+    async fn commit_add<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
+    where
+        C: Client + 'a,
+    {
+        let stable = self.stable_entries();
+        // moved entries are placed in front of their existing successor, starting with the
+        // last one, new entries in front of the next existing entry
+        let mut successors = vec![None; self.entries.len()];
+        let mut before: Option<String> = None;
+        for (index, (position, entry)) in self.entries.iter_mut().enumerate().rev() {
+            if position.is_none() {
+                successors[index] = before.clone();
+                continue;
+            }
+            if !stable[index] && !entry.is_dynamic() {
+                client.move_before(entry.clone(), before.as_deref()).await?;
+            }
+            if let Some(id) = id_of(entry) {
+                before = Some(id);
+            }
+        }
+        // in the desired order, so new entries never refer to each other: clients like
+        // `ConfigClient` cannot refer to entries they added
+        for ((position, entry), before) in self.entries.iter_mut().zip(successors) {
+            if position.is_none() && entry.is_modified() {
+                client.add_before(entry, before.as_deref()).await?;
+            }
+        }
+        Ok(())
+    }
+    fn resource_path(&self) -> &'static str {
+        R::resource_path()
+    }
+    fn referenced_menus(&self) -> Vec<&'static str> {
+        referenced_menus::<R>()
+    }
+    fn plan(&self) -> ChangePlan {
+        let mut plan = ChangePlan::new();
+        for entry in self.removed.iter().filter(|entry| !entry.is_dynamic()) {
+            plan.push(PlannedChange::delete(entry, Removal::Explicit));
+        }
+        for (position, entry) in self.entries.iter() {
+            if position.is_some() && !entry.is_dynamic() {
+                plan.extend(PlannedChange::update(entry, EntryRef::of(entry)));
+            }
+        }
+        let stable = self.stable_entries();
+        for (index, (position, entry)) in self.entries.iter().enumerate() {
+            if position.is_some() && !stable[index] && !entry.is_dynamic() {
+                plan.push(PlannedChange::move_to(entry, index));
+            }
+        }
+        for (index, (position, entry)) in self.entries.iter().enumerate() {
+            if position.is_none() && entry.is_modified() {
+                plan.push(PlannedChange::add(entry, Some(index)));
+            }
+        }
+        plan
+    }
+    async fn rollback<'a, C>(&'a mut self, client: &'a mut C) -> Result<(), RosError>
+    where
+        C: Client + 'a,
+    {
+        *self = OrderedListAccess::new(client.list().await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+use crate::client::config::ConfigClient;
+use crate::client::test_util::{bridge, client_with, name_of};
+use crate::client::{Client, ResourceAccess};
+use crate::generated::interface::bridge::Bridge;
+use crate::model::{RosFieldValue, RouterOsResource};
+
+#[tokio::test]
+async fn only_entries_out_of_order_are_moved() {
+    let mut client = client_with(&["a", "b", "c", "d"]).await;
+    let mut bridges = client.fetch_ordered::<Bridge>().await.unwrap();
+    let c = bridges.position(|bridge| name_of(bridge) == "c").unwrap();
+    bridges.move_to(c, 0);
+    bridges.insert(2, bridge("e"));
+    let d = bridges.position(|bridge| name_of(bridge) == "d").unwrap();
+    bridges.remove(d);
+    bridges.commit(&mut client).await.unwrap();
+
+    assert_eq!(
+        client.dump_cmd(),
+        "/interface bridge\n\
+         remove [ find where name=\"d\" ]\n\
+         move [ find where name=\"c\" ] destination=[ find where name=\"a\" ]\n\
+         add name=\"e\" place-before=[ find where name=\"b\" ]\n"
+    );
+    let names: Vec<String> = bridges.iter().map(name_of).collect();
+    assert_eq!(names, ["c", "a", "e", "b"]);
+    let stored: Vec<String> = client
+        .list::<Bridge>()
+        .await
+        .unwrap()
+        .iter()
+        .map(name_of)
+        .collect();
+    assert_eq!(stored, names);
+}
+
+#[tokio::test]
+async fn reversed_list_keeps_one_entry_in_place() {
+    let mut client = client_with(&["a", "b", "c", "d"]).await;
+    let mut bridges = client.fetch_ordered::<Bridge>().await.unwrap();
+    for index in 1..bridges.len() {
+        bridges.move_to(index, 0);
+    }
+    assert_eq!(
+        bridges
+            .stable_entries()
+            .iter()
+            .filter(|stable| **stable)
+            .count(),
+        1
+    );
+    bridges.commit(&mut client).await.unwrap();
+
+    let script = client.dump_cmd();
+    assert_eq!(
+        script
+            .lines()
+            .filter(|line| line.starts_with("move"))
+            .count(),
+        3
+    );
+    let stored: Vec<String> = client
+        .list::<Bridge>()
+        .await
+        .unwrap()
+        .iter()
+        .map(name_of)
+        .collect();
+    assert_eq!(stored, ["d", "c", "b", "a"]);
+}
+
+/// Menu like the firewall ones, entries have no key besides `.id`
+#[derive(Debug, Default, Clone, RouterOsResource)]
+#[ros(path = "ip/firewall/filter")]
+struct FilterRule {
+    #[ros(key, name = ".id")]
+    id: RosFieldValue<String>,
+    chain: RosFieldValue<String>,
+    action: RosFieldValue<String>,
+}
+
+fn rule(action: &str) -> FilterRule {
+    let mut rule = FilterRule::default();
+    rule.chain.set("input");
+    rule.action.set(action);
+    rule
+}
+
+#[tokio::test]
+async fn entries_without_key_are_added_in_order() {
+    let mut client = ConfigClient::new();
+    let mut rules = client.fetch_ordered::<FilterRule>().await.unwrap();
+    rules.push(rule("accept"));
+    rules.push(rule("drop"));
+    rules.insert(1, rule("log"));
+    rules.commit(&mut client).await.unwrap();
+    assert_eq!(
+        client.dump_cmd(),
+        "/ip firewall filter\n\
+         add chain=\"input\" action=\"accept\"\n\
+         add chain=\"input\" action=\"log\"\n\
+         add chain=\"input\" action=\"drop\"\n"
+    );
+
+    // the script cannot find the entries added before
+    let mut rules = client.fetch_ordered::<FilterRule>().await.unwrap();
+    rules.insert(0, rule("reject"));
+    assert!(rules.commit(&mut client).await.is_err());
+    let mut rules = client.fetch_ordered::<FilterRule>().await.unwrap();
+    rules.move_to(2, 0);
+    assert!(rules.commit(&mut client).await.is_err());
+    assert_eq!(client.dump_cmd(), "");
+}
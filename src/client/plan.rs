@@ -0,0 +1,329 @@
+//! Changes a [`crate::client::ResourceAccess`] would send on `commit`, to review them before
+//! anything is written to the router.
+//!
+//! A plan renders as a diff with [`Display`] and as JSON with [`ChangePlan::to_json`]:
+//!
+//! ```text
+//! - ip/address .id=*2 (untouched)
+//! ~ ip/address .id=*1
+//!     comment: lan -> uplink
+//! + ip/address
+//!     address: 172.16.0.1/16
+//!     interface: bridge
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use serde_json::{json, Value};
+
+use crate::model::{RosFieldAccessor, RouterOsResource, ValueFormat};
+
+#[cfg(test)]
+mod tests;
+
+/// How an entry came to be deleted
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Removal {
+    /// Removed with `remove`
+    Explicit,
+    /// Put aside and not taken back before the commit
+    Untouched,
+}
+
+/// Entry as it can be found on the router, by a key field other than `.id` if it has one
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EntryRef {
+    pub field: String,
+    pub value: String,
+}
+
+/// Value of an attribute before and after the change, `None` if it has no value
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum PlannedChange {
+    Add {
+        path: String,
+        /// Index in an ordered menu, `None` if the entry is appended
+        position: Option<usize>,
+        fields: Vec<FieldChange>,
+    },
+    Update {
+        path: String,
+        /// `None` for single resources
+        entry: Option<EntryRef>,
+        fields: Vec<FieldChange>,
+    },
+    Delete {
+        path: String,
+        entry: Option<EntryRef>,
+        removal: Removal,
+    },
+    /// Existing entry moved to `position` of an ordered menu
+    Move {
+        path: String,
+        entry: Option<EntryRef>,
+        position: usize,
+    },
+}
+
+/// Changes in the order `commit` sends them
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ChangePlan {
+    changes: Vec<PlannedChange>,
+}
+
+impl EntryRef {
+    pub fn of<R: RouterOsResource>(entry: &R) -> Option<EntryRef> {
+        let value_of = |field: &dyn RosFieldAccessor| {
+            field
+                .original_value(&ValueFormat::Api)
+                .unwrap_or_else(|| field.api_value(&ValueFormat::Api))
+        };
+        entry
+            .fields()
+            .find(|(description, field)| {
+                description.is_id && description.name != ".id" && field.has_value()
+            })
+            .or_else(|| entry.id_field())
+            .map(|(description, field)| EntryRef {
+                field: description.name.to_owned(),
+                value: value_of(field),
+            })
+    }
+}
+
+impl PlannedChange {
+    pub(crate) fn add<R: RouterOsResource>(entry: &R, position: Option<usize>) -> PlannedChange {
+        PlannedChange::Add {
+            path: R::resource_path().to_owned(),
+            position,
+            fields: entry
+                .fields()
+                .filter_map(|(description, field)| {
+                    field
+                        .modified_value(&ValueFormat::Api)
+                        .filter(|value| !value.is_empty())
+                        .map(|value| FieldChange {
+                            name: description.name.to_owned(),
+                            old: None,
+                            new: Some(value),
+                        })
+                })
+                .collect(),
+        }
+    }
+    /// Update of the modified attributes, `None` if nothing is modified
+    pub(crate) fn update<R: RouterOsResource>(
+        entry: &R,
+        entry_ref: Option<EntryRef>,
+    ) -> Option<PlannedChange> {
+        let fields: Vec<FieldChange> = entry
+            .fields()
+            .filter_map(|(description, field)| {
+                field
+                    .modified_value(&ValueFormat::Api)
+                    .map(|value| FieldChange {
+                        name: description.name.to_owned(),
+                        old: field.original_value(&ValueFormat::Api),
+                        new: Some(value).filter(|value| !value.is_empty()),
+                    })
+            })
+            .collect();
+        if fields.is_empty() {
+            None
+        } else {
+            Some(PlannedChange::Update {
+                path: R::resource_path().to_owned(),
+                entry: entry_ref,
+                fields,
+            })
+        }
+    }
+    pub(crate) fn delete<R: RouterOsResource>(entry: &R, removal: Removal) -> PlannedChange {
+        PlannedChange::Delete {
+            path: R::resource_path().to_owned(),
+            entry: EntryRef::of(entry),
+            removal,
+        }
+    }
+    pub(crate) fn move_to<R: RouterOsResource>(entry: &R, position: usize) -> PlannedChange {
+        PlannedChange::Move {
+            path: R::resource_path().to_owned(),
+            entry: EntryRef::of(entry),
+            position,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            PlannedChange::Add { path, .. }
+            | PlannedChange::Update { path, .. }
+            | PlannedChange::Delete { path, .. }
+            | PlannedChange::Move { path, .. } => path,
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let entry_json = |entry: &Option<EntryRef>| {
+            entry
+                .as_ref()
+                .map(|entry| json!({"field": entry.field, "value": entry.value}))
+        };
+        let fields_json = |fields: &[FieldChange]| -> Vec<Value> {
+            fields
+                .iter()
+                .map(|field| json!({"name": field.name, "old": field.old, "new": field.new}))
+                .collect()
+        };
+        match self {
+            PlannedChange::Add {
+                path,
+                position,
+                fields,
+            } => json!({
+                "action": "add",
+                "path": path,
+                "position": position,
+                "fields": fields_json(fields),
+            }),
+            PlannedChange::Update {
+                path,
+                entry,
+                fields,
+            } => json!({
+                "action": "update",
+                "path": path,
+                "entry": entry_json(entry),
+                "fields": fields_json(fields),
+            }),
+            PlannedChange::Delete {
+                path,
+                entry,
+                removal,
+            } => json!({
+                "action": "delete",
+                "path": path,
+                "entry": entry_json(entry),
+                "removal": match removal {
+                    Removal::Explicit => "explicit",
+                    Removal::Untouched => "untouched",
+                },
+            }),
+            PlannedChange::Move {
+                path,
+                entry,
+                position,
+            } => json!({
+                "action": "move",
+                "path": path,
+                "entry": entry_json(entry),
+                "position": position,
+            }),
+        }
+    }
+}
+
+impl ChangePlan {
+    pub fn new() -> ChangePlan {
+        Self::default()
+    }
+    pub fn push(&mut self, change: PlannedChange) {
+        self.changes.push(change);
+    }
+    /// Appends the changes of `other`, e.g. of the next menu committed
+    pub fn append(&mut self, mut other: ChangePlan) {
+        self.changes.append(&mut other.changes);
+    }
+    pub fn changes(&self) -> &[PlannedChange] {
+        &self.changes
+    }
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+    pub fn to_json(&self) -> Value {
+        json!({
+            "changes": self.changes.iter().map(PlannedChange::to_json).collect::<Vec<_>>(),
+        })
+    }
+}
+
+impl Extend<PlannedChange> for ChangePlan {
+    fn extend<T: IntoIterator<Item = PlannedChange>>(&mut self, iter: T) {
+        self.changes.extend(iter);
+    }
+}
+
+fn optional_value(value: &Option<String>) -> &str {
+    value.as_deref().unwrap_or("(unset)")
+}
+
+impl Display for EntryRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.field, self.value)
+    }
+}
+
+impl Display for FieldChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.name,
+            optional_value(&self.old),
+            optional_value(&self.new)
+        )
+    }
+}
+
+impl Display for PlannedChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (sign, entry, fields) = match self {
+            PlannedChange::Add { fields, .. } => ('+', None, fields.as_slice()),
+            PlannedChange::Update { entry, fields, .. } => ('~', entry.as_ref(), fields.as_slice()),
+            PlannedChange::Delete { entry, .. } => ('-', entry.as_ref(), &[][..]),
+            PlannedChange::Move { entry, .. } => ('>', entry.as_ref(), &[][..]),
+        };
+        write!(f, "{sign} {}", self.path())?;
+        if let Some(entry) = entry {
+            write!(f, " {entry}")?;
+        }
+        match self {
+            PlannedChange::Add {
+                position: Some(position),
+                ..
+            }
+            | PlannedChange::Move { position, .. } => write!(f, " at {position}")?,
+            PlannedChange::Delete {
+                removal: Removal::Untouched,
+                ..
+            } => f.write_str(" (untouched)")?,
+            _ => {}
+        }
+        for field in fields {
+            if let PlannedChange::Add { .. } = self {
+                write!(f, "\n    {}: {}", field.name, optional_value(&field.new))?;
+            } else {
+                write!(f, "\n    {field}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for ChangePlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
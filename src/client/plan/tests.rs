@@ -0,0 +1,83 @@
+use serde_json::json;
+
+use crate::client::test_util::{client_with, name_of};
+use crate::client::{Client, ResourceAccess};
+use crate::field_ref;
+use crate::generated::interface::bridge::Bridge;
+use crate::generated::system::identity::Identity;
+use crate::model::ros_value::Auto;
+
+#[tokio::test]
+async fn plan_lists_changes_without_sending_them() {
+    let mut client = client_with(&["a", "b", "c"]).await;
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    for bridge in bridges.find_mut(|bridge| name_of(bridge) == "a") {
+        bridge.mtu.set(Auto::Value(1500));
+    }
+    bridges.remove(|bridge| name_of(bridge) == "b");
+    bridges.put_aside(&|bridge: &Bridge| name_of(bridge) == "c");
+    bridges.get_or_create_by_value(&field_ref!(Bridge => name), String::from("d"));
+
+    let plan = bridges.plan();
+    assert_eq!(
+        plan.to_string(),
+        "- interface/bridge name=b\n\
+         - interface/bridge name=c (untouched)\n\
+         ~ interface/bridge name=a\n    mtu: (unset) -> 1500\n\
+         + interface/bridge\n    name: d\n"
+    );
+    assert_eq!(
+        plan.to_json()["changes"][1],
+        json!({
+            "action": "delete",
+            "path": "interface/bridge",
+            "entry": {"field": "name", "value": "c"},
+            "removal": "untouched",
+        })
+    );
+    assert_eq!(
+        plan.to_json()["changes"][2]["fields"],
+        json!([{"name": "mtu", "old": null, "new": "1500"}])
+    );
+    assert_eq!(client.dump_cmd(), "");
+
+    bridges.commit(&mut client).await.unwrap();
+    assert!(bridges.plan().is_empty());
+}
+
+#[tokio::test]
+async fn new_entries_without_values_are_not_planned() {
+    let mut client = client_with(&["a"]).await;
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    bridges.get_or_default(|bridge| name_of(bridge) == "b");
+    assert!(bridges.plan().is_empty());
+    bridges.commit(&mut client).await.unwrap();
+    assert_eq!(client.dump_cmd(), "");
+
+    let mut bridges = client.fetch_ordered::<Bridge>().await.unwrap();
+    bridges.push(Bridge::default());
+    assert!(bridges.plan().is_empty());
+    bridges.commit(&mut client).await.unwrap();
+    assert_eq!(client.dump_cmd(), "");
+}
+
+#[tokio::test]
+async fn single_and_ordered_resources_are_planned() {
+    let mut client = client_with(&["a", "b"]).await;
+    let mut identity = client.get::<Identity>().await.unwrap();
+    identity.name.set("router");
+    assert_eq!(
+        identity.plan().to_string(),
+        "~ system/identity\n    name: (unset) -> router\n"
+    );
+
+    let mut bridges = client.fetch_ordered::<Bridge>().await.unwrap();
+    bridges.move_to(1, 0);
+    let mut bridge = Bridge::default();
+    bridge.name.set("c");
+    bridges.insert(1, bridge);
+    assert_eq!(
+        bridges.plan().to_string(),
+        "> interface/bridge name=b at 0\n+ interface/bridge at 1\n    name: c\n"
+    );
+}
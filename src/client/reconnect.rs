@@ -0,0 +1,357 @@
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::task::JoinHandle;
+
+use crate::client::api::{ApiClient, Transport};
+use crate::client::command::{Command, CommandOutput};
+use crate::client::filter::{Filter, Projection};
+use crate::client::version::UnsupportedFields;
+use crate::client::Client;
+use crate::model::dynamic::{DynamicMenu, DynamicResource};
+use crate::model::extra::{AttributeParser, ParseMode, ParseWarning};
+use crate::model::{RouterOsListResource, RouterOsResource, RouterOsSingleResource};
+use crate::RosError;
+
+/// How often and how fast failed operations are retried after the connection broke
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts including the first one
+    pub max_attempts: u32,
+    /// Wait time before the first retry, doubled for every further retry
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Fails on the first error
+    pub fn no_retry() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    fn backoff(&self, retry: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+}
+
+struct Session {
+    target: SocketAddr,
+    username: String,
+    password: String,
+    transport: Transport,
+    client: tokio::sync::Mutex<Option<ApiClient>>,
+}
+
+impl Session {
+    /// Current connection, logs in again if there is none or it was closed
+    async fn client(&self) -> Result<ApiClient, RosError> {
+        let mut client = self.client.lock().await;
+        match client.as_ref() {
+            Some(current) if !current.is_closed() => Ok(current.clone()),
+            _ => {
+                let connected = ApiClient::connect(
+                    self.target,
+                    self.username.clone(),
+                    self.password.clone(),
+                    self.transport.clone(),
+                )
+                .await?;
+                *client = Some(connected.clone());
+                Ok(connected)
+            }
+        }
+    }
+
+    /// Forgets the connection after `failed` failed on it, the next request connects again.
+    ///
+    /// Requests and the keepalive may notice the failure after another request already
+    /// connected again, the new connection is kept then.
+    async fn reset(&self, failed: &ApiClient) {
+        let mut client = self.client.lock().await;
+        if matches!(client.as_ref(), Some(current) if current.same_connection(failed)) {
+            *client = None;
+        }
+    }
+
+    async fn keep_alive(self: Arc<Self>, interval: Duration) {
+        let mut ticks = tokio::time::interval(interval);
+        ticks.tick().await;
+        loop {
+            ticks.tick().await;
+            let client = match self.client.lock().await.clone() {
+                Some(client) => client,
+                None => continue,
+            };
+            match tokio::time::timeout(interval, client.keepalive()).await {
+                Ok(Ok(())) => {}
+                Ok(Err(error)) if !error.is_connection_error() => {}
+                _ => self.reset(&client).await,
+            }
+        }
+    }
+}
+
+/// [`ApiClient`] that logs in again after the connection was lost.
+///
+/// Reads and `set`/`update`, which are safe to repeat, are retried according to the
+/// [`RetryPolicy`] when they fail because of the connection. `add`, `delete` and commands
+/// are sent once, a broken connection is only replaced before they are sent.
+pub struct ReconnectingClient {
+    session: Arc<Session>,
+    retry_policy: RetryPolicy,
+    keepalive_interval: Option<Duration>,
+    keepalive: Mutex<Option<JoinHandle<()>>>,
+    unsupported_fields: UnsupportedFields,
+    parser: AttributeParser,
+}
+
+impl Drop for ReconnectingClient {
+    fn drop(&mut self) {
+        if let Some(keepalive) = self.keepalive.lock().unwrap().take() {
+            keepalive.abort();
+        }
+    }
+}
+
+impl ReconnectingClient {
+    /// Creates the client, the connection is opened on the first request
+    pub fn new(
+        target: SocketAddr,
+        username: String,
+        password: String,
+        transport: Transport,
+    ) -> ReconnectingClient {
+        ReconnectingClient {
+            session: Arc::new(Session {
+                target,
+                username,
+                password,
+                transport,
+                client: tokio::sync::Mutex::new(None),
+            }),
+            retry_policy: RetryPolicy::default(),
+            keepalive_interval: Some(Duration::from_secs(60)),
+            keepalive: Mutex::new(None),
+            unsupported_fields: UnsupportedFields::default(),
+            parser: AttributeParser::default(),
+        }
+    }
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> ReconnectingClient {
+        self.retry_policy = retry_policy;
+        self
+    }
+    /// Interval of the keepalive commands, `None` disables them
+    pub fn keepalive(mut self, interval: Option<Duration>) -> ReconnectingClient {
+        self.keepalive_interval = interval;
+        self
+    }
+
+    /// What happens to modified fields the version of the router does not support, the
+    /// version is read again on every login
+    pub fn unsupported_fields(mut self, unsupported: UnsupportedFields) -> ReconnectingClient {
+        self.unsupported_fields = unsupported;
+        self
+    }
+
+    /// Sets how attributes that do not fit the resources are treated, see
+    /// [`crate::model::extra`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> ReconnectingClient {
+        self.parser.mode = mode;
+        self
+    }
+
+    /// Currently used connection, e.g. to start streams on it
+    pub async fn client(&self) -> Result<ApiClient, RosError> {
+        let client = self.session.client().await?;
+        if let Some(interval) = self.keepalive_interval {
+            let mut keepalive = self.keepalive.lock().unwrap();
+            if keepalive.is_none() {
+                *keepalive = Some(tokio::spawn(self.session.clone().keep_alive(interval)));
+            }
+        }
+        Ok(client
+            .unsupported_fields(self.unsupported_fields)
+            .with_parser(self.parser.clone()))
+    }
+
+    async fn retry<T, O, F>(&self, mut operation: O) -> Result<T, RosError>
+    where
+        O: FnMut(ApiClient) -> F,
+        F: Future<Output = Result<T, RosError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let (result, used) = match self.client().await {
+                Ok(client) => (operation(client.clone()).await, Some(client)),
+                Err(error) => (Err(error), None),
+            };
+            match result {
+                Err(error) if error.is_connection_error() => {
+                    if let Some(used) = used {
+                        self.session.reset(&used).await;
+                    }
+                    attempt += 1;
+                    if attempt >= self.retry_policy.max_attempts {
+                        return Err(error);
+                    }
+                    tokio::time::sleep(self.retry_policy.backoff(attempt - 1)).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn once<T, O, F>(&self, operation: O) -> Result<T, RosError>
+    where
+        O: FnOnce(ApiClient) -> F,
+        F: Future<Output = Result<T, RosError>>,
+    {
+        let client = self.client().await?;
+        let result = operation(client.clone()).await;
+        if matches!(&result, Err(error) if error.is_connection_error()) {
+            self.session.reset(&client).await;
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl Client for ReconnectingClient {
+    async fn list<Resource>(&mut self) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.retry(|mut client| async move { client.list().await })
+            .await
+    }
+
+    async fn list_where<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.retry(|mut client| async move { client.list_where(filter).await })
+            .await
+    }
+
+    async fn list_projected<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+        projection: &Projection<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.retry(|mut client| async move { client.list_projected(filter, projection).await })
+            .await
+    }
+
+    async fn update<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        self.retry(move |mut client| {
+            let resource = resource.clone();
+            async move { client.update(resource).await }
+        })
+        .await
+    }
+
+    async fn set<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsSingleResource,
+    {
+        self.retry(move |mut client| {
+            let resource = resource.clone();
+            async move { Client::set(&mut client, resource).await }
+        })
+        .await
+    }
+
+    async fn add_before<Resource>(
+        &mut self,
+        resource: &mut Resource,
+        before: Option<&str>,
+    ) -> Result<Option<String>, RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        self.once(|mut client| async move { client.add_before(resource, before).await })
+            .await
+    }
+
+    async fn move_before<Resource>(
+        &mut self,
+        resource: Resource,
+        before: Option<&str>,
+    ) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        self.once(|mut client| async move { client.move_before(resource, before).await })
+            .await
+    }
+
+    async fn delete<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        self.once(|mut client| async move { client.delete(resource).await })
+            .await
+    }
+
+    async fn list_dynamic(&mut self, menu: &DynamicMenu) -> Result<Vec<DynamicResource>, RosError> {
+        self.retry(|mut client| async move { client.list_dynamic(menu).await })
+            .await
+    }
+
+    async fn update_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        self.retry(move |mut client| {
+            let resource = resource.clone();
+            async move { client.update_dynamic(resource).await }
+        })
+        .await
+    }
+
+    async fn add_dynamic(
+        &mut self,
+        resource: &mut DynamicResource,
+    ) -> Result<Option<String>, RosError> {
+        self.once(|mut client| async move { client.add_dynamic(resource).await })
+            .await
+    }
+
+    async fn delete_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        self.once(|mut client| async move { client.delete_dynamic(resource).await })
+            .await
+    }
+
+    async fn execute(&mut self, command: &Command) -> Result<CommandOutput, RosError> {
+        self.once(|mut client| async move { client.execute(command).await })
+            .await
+    }
+
+    fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
+}
@@ -0,0 +1,295 @@
+//! Checks of the references declared with `->` in `ros_model` across resource accesses.
+//!
+//! [`References`] knows the entries of the registered accesses as they will be after the
+//! commit. A reference is checked when all menus it may point to are registered, e.g. the
+//! `interface` of an address only if `/interface` is, which lists the interfaces of every
+//! type. Interfaces added in the same commit are found if the menu of their type, like
+//! bridges, is registered too.
+//!
+//! ```ignore
+//! let mut references = References::new();
+//! references.register(&mut bridges).register(&mut ports);
+//! references.rename("interface/bridge", "name", "bridge1", "br-lan")?;
+//! references.check()?;
+//! ```
+
+use std::fmt::{Display, Formatter};
+
+use crate::client::ordered::OrderedListAccess;
+use crate::client::{ResourceListAccess, ResourceSingleAccess};
+use crate::model::{
+    FieldDescription, FieldReference, RouterOsListResource, RouterOsResource,
+    RouterOsSingleResource, ValueFormat,
+};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Field values of an entry in API format
+pub type EntryValues = Vec<(&'static FieldDescription, String)>;
+
+/// Entries of a resource access as seen by [`References`]
+pub trait ReferenceAccess: Send {
+    fn resource_path(&self) -> &'static str;
+    /// Current values of the entries that remain after the commit
+    fn entry_values(&self) -> Vec<EntryValues>;
+    /// Replaces `old` by `new` in the fields `matches` selects, also within lists of values,
+    /// and returns the number of fields changed
+    fn replace_values(
+        &mut self,
+        matches: &dyn Fn(&FieldDescription) -> bool,
+        old: &str,
+        new: &str,
+    ) -> Result<usize, RosError>;
+}
+
+fn entry_values_of<'r, R, I>(entries: I) -> Vec<EntryValues>
+where
+    R: RouterOsResource + 'r,
+    I: Iterator<Item = &'r R>,
+{
+    entries
+        .map(|entry| {
+            entry
+                .fields()
+                .map(|(description, field)| (description, field.api_value(&ValueFormat::Api)))
+                .collect()
+        })
+        .collect()
+}
+
+fn replace_in<'r, R, I>(
+    entries: I,
+    matches: &dyn Fn(&FieldDescription) -> bool,
+    old: &str,
+    new: &str,
+) -> Result<usize, RosError>
+where
+    R: RouterOsResource + 'r,
+    I: Iterator<Item = &'r mut R>,
+{
+    let mut ret = 0;
+    for entry in entries {
+        for (description, field) in entry.fields_mut() {
+            if !matches(description) {
+                continue;
+            }
+            let value = field.api_value(&ValueFormat::Api);
+            if !value.split(',').any(|item| item == old) {
+                continue;
+            }
+            let replaced: Vec<&str> = value
+                .split(',')
+                .map(|item| if item == old { new } else { item })
+                .collect();
+            field.set_api_value(&replaced.join(","))?;
+            ret += 1;
+        }
+    }
+    Ok(ret)
+}
+
+impl<R: RouterOsListResource> ReferenceAccess for ResourceListAccess<R> {
+    fn resource_path(&self) -> &'static str {
+        R::resource_path()
+    }
+    fn entry_values(&self) -> Vec<EntryValues> {
+        entry_values_of(self.iter())
+    }
+    fn replace_values(
+        &mut self,
+        matches: &dyn Fn(&FieldDescription) -> bool,
+        old: &str,
+        new: &str,
+    ) -> Result<usize, RosError> {
+        replace_in(self.iter_mut(), matches, old, new)
+    }
+}
+
+impl<R: RouterOsListResource> ReferenceAccess for OrderedListAccess<R> {
+    fn resource_path(&self) -> &'static str {
+        R::resource_path()
+    }
+    fn entry_values(&self) -> Vec<EntryValues> {
+        entry_values_of(self.iter())
+    }
+    fn replace_values(
+        &mut self,
+        matches: &dyn Fn(&FieldDescription) -> bool,
+        old: &str,
+        new: &str,
+    ) -> Result<usize, RosError> {
+        replace_in(self.iter_mut(), matches, old, new)
+    }
+}
+
+impl<R: RouterOsSingleResource> ReferenceAccess for ResourceSingleAccess<R> {
+    fn resource_path(&self) -> &'static str {
+        R::resource_path()
+    }
+    fn entry_values(&self) -> Vec<EntryValues> {
+        entry_values_of(std::iter::once(&self.data))
+    }
+    fn replace_values(
+        &mut self,
+        matches: &dyn Fn(&FieldDescription) -> bool,
+        old: &str,
+        new: &str,
+    ) -> Result<usize, RosError> {
+        replace_in(std::iter::once(&mut self.data), matches, old, new)
+    }
+}
+
+/// Value that refers to no entry of the menus its field points to
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DanglingReference {
+    pub path: &'static str,
+    /// Key field and value of the referring entry, `None` if it has no key yet
+    pub entry: Option<(&'static str, String)>,
+    pub field: &'static str,
+    pub value: String,
+}
+
+impl Display for DanglingReference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.path)?;
+        if let Some((key, value)) = &self.entry {
+            write!(f, " {key}={value}")?;
+        }
+        write!(
+            f,
+            ": {}={} refers to a missing entry",
+            self.field, self.value
+        )
+    }
+}
+
+/// Resource accesses whose references are checked together, see the module documentation
+#[derive(Default)]
+pub struct References<'a> {
+    accesses: Vec<&'a mut dyn ReferenceAccess>,
+}
+
+impl<'a> References<'a> {
+    pub fn new() -> References<'a> {
+        Self::default()
+    }
+    pub fn register<A: ReferenceAccess>(&mut self, access: &'a mut A) -> &mut Self {
+        self.accesses.push(access);
+        self
+    }
+
+    /// References whose target menus are all registered and that match no entry
+    pub fn validate(&self) -> Vec<DanglingReference> {
+        let entries: Vec<(&str, Vec<EntryValues>)> = self
+            .accesses
+            .iter()
+            .map(|access| (access.resource_path(), access.entry_values()))
+            .collect();
+        let is_registered = |path: &str| entries.iter().any(|(p, _)| *p == path);
+        // a target like `interface` lists the entries of the targets below it
+        let is_covered = |targets: &[FieldReference], path: &str| {
+            is_registered(path)
+                || targets.iter().any(|covering| {
+                    is_registered(covering.path)
+                        && path
+                            .strip_prefix(covering.path)
+                            .is_some_and(|below| below.starts_with('/'))
+                })
+        };
+        let exists = |path: &str, field: &str, value: &str| {
+            entries
+                .iter()
+                .filter(|(p, _)| *p == path)
+                .flat_map(|(_, values)| values)
+                .any(|entry| {
+                    entry
+                        .iter()
+                        .any(|(description, v)| description.name == field && v == value)
+                })
+        };
+        let mut ret = Vec::new();
+        for (path, values) in &entries {
+            for entry in values {
+                let key = entry
+                    .iter()
+                    .find(|(description, value)| {
+                        description.is_id && description.name != ".id" && !value.is_empty()
+                    })
+                    .or_else(|| {
+                        entry
+                            .iter()
+                            .find(|(description, value)| description.is_id && !value.is_empty())
+                    })
+                    .map(|(description, value)| (description.name, value.clone()));
+                for (description, value) in entry {
+                    let targets = description.references;
+                    if targets.is_empty()
+                        || !targets
+                            .iter()
+                            .all(|target| is_covered(targets, target.path))
+                    {
+                        continue;
+                    }
+                    for item in value.split(',').filter(|item| !item.is_empty()) {
+                        if !targets
+                            .iter()
+                            .any(|target| exists(target.path, target.field, item))
+                        {
+                            ret.push(DanglingReference {
+                                path,
+                                entry: key.clone(),
+                                field: description.name,
+                                value: item.to_owned(),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        ret
+    }
+
+    /// Fails with the dangling references, if there are any
+    pub fn check(&self) -> Result<(), RosError> {
+        let dangling = self.validate();
+        if dangling.is_empty() {
+            Ok(())
+        } else {
+            Err(RosError::Umbrella(
+                dangling
+                    .iter()
+                    .map(|reference| RosError::SimpleMessage(reference.to_string()))
+                    .collect(),
+            ))
+        }
+    }
+
+    /// Changes `field` of the entry of `path` from `old` to `new` together with every field
+    /// that refers to it, e.g. `rename("interface/bridge", "name", "bridge1", "br-lan")`.
+    ///
+    /// Returns the number of fields changed, including the renamed one.
+    pub fn rename(
+        &mut self,
+        path: &str,
+        field: &str,
+        old: &str,
+        new: &str,
+    ) -> Result<usize, RosError> {
+        let path = path.trim_matches('/');
+        let mut ret = 0;
+        for access in self.accesses.iter_mut() {
+            let is_target = access.resource_path() == path;
+            let matches = |description: &FieldDescription| {
+                (is_target && description.name == field)
+                    || description
+                        .references
+                        .iter()
+                        .any(|target| target.path == path && target.field == field)
+            };
+            ret += access.replace_values(&matches, old, new)?;
+        }
+        Ok(ret)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::client::config::ConfigClient;
+use crate::client::references::{DanglingReference, References};
+use crate::client::{Client, ResourceListAccess};
+use crate::generated::interface::bridge::port::BridgePort;
+use crate::generated::interface::bridge::Bridge;
+use crate::generated::interface::ethernet::Ethernet;
+use crate::generated::interface::vlan::Vlan;
+use crate::generated::interface::wireless::Wireless;
+use crate::generated::interface::Interface;
+use crate::generated::ip::address::Address;
+use crate::model::ros_value::RosFieldAccessor;
+use crate::model::ValueFormat;
+
+struct Interfaces {
+    all: ResourceListAccess<Interface>,
+    bridges: ResourceListAccess<Bridge>,
+    ethernet: ResourceListAccess<Ethernet>,
+    vlans: ResourceListAccess<Vlan>,
+    wireless: ResourceListAccess<Wireless>,
+}
+
+async fn interfaces(client: &mut ConfigClient) -> Interfaces {
+    let mut ret = Interfaces {
+        all: client.fetch().await.unwrap(),
+        bridges: client.fetch().await.unwrap(),
+        ethernet: client.fetch().await.unwrap(),
+        vlans: client.fetch().await.unwrap(),
+        wireless: client.fetch().await.unwrap(),
+    };
+    for name in ["lo", "ether1", "wireguard1"] {
+        ret.all.get_or_default(|_| false).name.set(name);
+    }
+    ret.bridges.get_or_default(|_| false).name.set("bridge1");
+    ret.ethernet.get_or_default(|_| false).name.set("ether1");
+    let vlan = ret.vlans.get_or_default(|_| false);
+    vlan.name.set("vlan10");
+    vlan.interface.set("bridge1");
+    ret
+}
+
+#[tokio::test]
+async fn dangling_references_are_reported() {
+    let mut client = ConfigClient::new();
+    let mut interfaces = interfaces(&mut client).await;
+    let mut ports = client.fetch::<BridgePort>().await.unwrap();
+    let port = ports.get_or_default(|_| false);
+    port.interface.set("ether1");
+    port.bridge.set("br9");
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    for interface in ["vlan10", "wireguard1", "wireguard9"] {
+        addresses.get_or_default(|_| false).interface.set(interface);
+    }
+
+    let mut references = References::new();
+    references.register(&mut ports).register(&mut addresses);
+    assert_eq!(references.validate(), []);
+
+    let missing_bridge = DanglingReference {
+        path: "interface/bridge/port",
+        entry: None,
+        field: "bridge",
+        value: String::from("br9"),
+    };
+    references
+        .register(&mut interfaces.bridges)
+        .register(&mut interfaces.ethernet)
+        .register(&mut interfaces.vlans)
+        .register(&mut interfaces.wireless);
+    // interfaces of other types like wireguard are only listed in `/interface`
+    assert_eq!(references.validate(), std::slice::from_ref(&missing_bridge));
+    assert_eq!(
+        references.check().unwrap_err().to_string(),
+        "interface/bridge/port: bridge=br9 refers to a missing entry"
+    );
+
+    references.register(&mut interfaces.all);
+    assert_eq!(
+        references.validate(),
+        [
+            missing_bridge,
+            DanglingReference {
+                path: "ip/address",
+                entry: None,
+                field: "interface",
+                value: String::from("wireguard9"),
+            }
+        ]
+    );
+}
+
+#[tokio::test]
+async fn renames_are_applied_to_referring_fields() {
+    let mut client = ConfigClient::new();
+    let mut interfaces = interfaces(&mut client).await;
+    let mut ports = client.fetch::<BridgePort>().await.unwrap();
+    let port = ports.get_or_default(|_| false);
+    port.interface.set("ether1");
+    port.bridge.set("bridge1");
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    addresses.get_or_default(|_| false).interface.set("bridge1");
+
+    let mut references = References::new();
+    references
+        .register(&mut interfaces.all)
+        .register(&mut interfaces.bridges)
+        .register(&mut interfaces.ethernet)
+        .register(&mut interfaces.vlans)
+        .register(&mut interfaces.wireless)
+        .register(&mut ports)
+        .register(&mut addresses);
+    assert_eq!(
+        references
+            .rename("/interface/bridge", "name", "bridge1", "br-lan")
+            .unwrap(),
+        4
+    );
+    references.check().unwrap();
+
+    let interface_of = |address: &Address| address.interface.api_value(&ValueFormat::Api);
+    assert_eq!(
+        addresses.iter().map(interface_of).collect::<Vec<_>>(),
+        ["br-lan"]
+    );
+    let bridge = ports.iter().next().unwrap();
+    assert_eq!(bridge.bridge.api_value(&ValueFormat::Api), "br-lan");
+    assert_eq!(bridge.interface.api_value(&ValueFormat::Api), "ether1");
+    assert_eq!(
+        interfaces
+            .vlans
+            .iter()
+            .next()
+            .unwrap()
+            .interface
+            .api_value(&ValueFormat::Api),
+        "br-lan"
+    );
+}
@@ -0,0 +1,520 @@
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder, Response};
+use serde_json::{Map, Value};
+
+use crate::client::command::{Command, CommandOutput};
+use crate::client::filter::{Filter, Projection, Query};
+use crate::client::tls::TlsConfig;
+use crate::client::version::{router_version, UnsupportedFields, VersionFilter};
+use crate::client::Client;
+use crate::model::dynamic::{DynamicMenu, DynamicResource};
+use crate::model::extra::{AttributeParser, ParseMode, ParseWarning};
+use crate::model::version::RosVersion;
+use crate::model::{RouterOsListResource, RouterOsResource, RouterOsSingleResource, ValueFormat};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Client for the REST api of RouterOS 7, served by the `www-ssl` service
+pub struct RestClient {
+    client: reqwest::Client,
+    base_url: String,
+    username: String,
+    password: String,
+    versions: VersionFilter,
+    parser: AttributeParser,
+}
+
+impl RestClient {
+    pub fn new(
+        target: IpAddr,
+        username: String,
+        password: String,
+        tls: TlsConfig,
+    ) -> Result<RestClient, RosError> {
+        let host = match target {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{ip}]"),
+        };
+        Self::with_base_url(format!("https://{host}/rest"), username, password, tls)
+    }
+
+    /// Creates the client and reads the RouterOS version of the router
+    pub async fn connect(
+        target: IpAddr,
+        username: String,
+        password: String,
+        tls: TlsConfig,
+    ) -> Result<RestClient, RosError> {
+        let mut client = Self::new(target, username, password, tls)?;
+        client.detect_version().await?;
+        Ok(client)
+    }
+
+    /// Creates a client for the rest api below `base_url`, e.g. `https://proxy/router1/rest`
+    pub fn with_base_url(
+        base_url: String,
+        username: String,
+        password: String,
+        tls: TlsConfig,
+    ) -> Result<RestClient, RosError> {
+        let client = reqwest::Client::builder()
+            .use_preconfigured_tls(tls.client_config()?)
+            .build()?;
+        Ok(RestClient {
+            client,
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            username,
+            password,
+            versions: VersionFilter::default(),
+            parser: AttributeParser::default(),
+        })
+    }
+
+    /// Reads the RouterOS version of the router, fields it does not support are not sent
+    /// afterwards, see [`crate::client::version`]
+    pub async fn detect_version(&mut self) -> Result<Option<RosVersion>, RosError> {
+        self.versions.version = router_version(self).await?;
+        Ok(self.versions.version)
+    }
+
+    /// RouterOS version of the router, if it was detected
+    pub fn version(&self) -> Option<RosVersion> {
+        self.versions.version
+    }
+
+    /// Sets what happens to modified fields the router version does not support
+    pub fn unsupported_fields(mut self, unsupported: UnsupportedFields) -> RestClient {
+        self.versions.unsupported = unsupported;
+        self
+    }
+
+    /// Sets how attributes that do not fit the resources are treated, see
+    /// [`crate::model::extra`]
+    pub fn parse_mode(mut self, mode: ParseMode) -> RestClient {
+        self.parser.mode = mode;
+        self
+    }
+
+    fn url<Resource: RouterOsResource>(&self, suffix: Option<&str>) -> String {
+        self.menu_url(Resource::resource_path(), suffix)
+    }
+
+    fn menu_url(&self, path: &str, suffix: Option<&str>) -> String {
+        match suffix {
+            Some(suffix) => format!("{}/{}/{}", self.base_url, path, suffix),
+            None => format!("{}/{}", self.base_url, path),
+        }
+    }
+
+    fn request(&self, method: Method, url: String) -> RequestBuilder {
+        self.client
+            .request(method, url)
+            .basic_auth(&self.username, Some(&self.password))
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Value, RosError> {
+        let response: Response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+        if !status.is_success() {
+            return Err(Self::error_of(status.as_u16(), &body));
+        }
+        if body.is_empty() {
+            Ok(Value::Null)
+        } else {
+            Ok(serde_json::from_slice(&body)?)
+        }
+    }
+
+    /// Error of a failed request, proxies and the web server may answer with plain text or
+    /// html instead of the json error of the rest api
+    fn error_of(status: u16, body: &[u8]) -> RosError {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(Value::Object(value)) => {
+                let text = |key| {
+                    value
+                        .get(key)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_owned()
+                };
+                RosError::RestError {
+                    status,
+                    message: text("message"),
+                    detail: text("detail"),
+                }
+            }
+            _ => RosError::RestError {
+                status,
+                message: String::from_utf8_lossy(body).trim().to_owned(),
+                detail: String::new(),
+            },
+        }
+    }
+
+    fn modified_fields<Resource: RouterOsResource>(
+        &self,
+        resource: &Resource,
+    ) -> Result<Map<String, Value>, RosError> {
+        Ok(self
+            .versions
+            .modified_values(resource, &ValueFormat::Api)?
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), Value::String(value)))
+            .collect())
+    }
+
+    /// Lists entries with the `print` command, which accepts queries and a proplist
+    async fn print<Resource: RouterOsResource>(
+        &self,
+        filter: &Filter<Resource>,
+        projection: Option<&Projection<Resource>>,
+    ) -> Result<Vec<Resource>, RosError> {
+        let mut body = Map::new();
+        body.insert(
+            String::from(".query"),
+            filter
+                .queries()?
+                .iter()
+                .map(|query| Value::String(rest_query(query)))
+                .collect(),
+        );
+        if let Some(projection) = projection {
+            body.insert(
+                String::from(".proplist"),
+                Value::String(projection.proplist()?),
+            );
+        }
+        self.parse_records(
+            Self::send(
+                self.request(Method::POST, self.url::<Resource>(Some("print")))
+                    .json(&body),
+            )
+            .await?,
+        )
+    }
+
+    fn parse_records<Resource: RouterOsResource>(
+        &self,
+        response: Value,
+    ) -> Result<Vec<Resource>, RosError> {
+        let records = match response {
+            Value::Array(records) => records,
+            Value::Null => Vec::new(),
+            record => vec![record],
+        };
+        records
+            .into_iter()
+            .map(|record| match record {
+                Value::Object(record) => self.parse_resource(record),
+                other => Err(RosError::SimpleMessage(format!(
+                    "Unexpected rest response: {other}"
+                ))),
+            })
+            .collect()
+    }
+
+    fn parse_resource<Resource: RouterOsResource>(
+        &self,
+        record: Map<String, Value>,
+    ) -> Result<Resource, RosError> {
+        let mut resource = Resource::default();
+        for (key, value) in record {
+            self.parser
+                .write(&mut resource, &key, &json_to_api(value))?;
+        }
+        Ok(resource)
+    }
+
+    fn dynamic_fields(resource: &DynamicResource) -> Map<String, Value> {
+        resource
+            .modified_values(&ValueFormat::Api)
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), Value::String(value)))
+            .collect()
+    }
+
+    fn id_of<Resource: RouterOsResource>(resource: &Resource) -> Option<String> {
+        resource
+            .id_field()
+            .and_then(|(_, field)| field.original_value(&ValueFormat::Api))
+    }
+}
+
+fn json_to_api(value: Value) -> String {
+    match value {
+        Value::String(value) => value,
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+/// Query words of the `print` command as the rest api expects them, without the leading `?`
+fn rest_query(query: &Query) -> String {
+    match query {
+        Query::HasValue(key) => key.clone(),
+        Query::HasNoValue(key) => format!("-{key}"),
+        Query::Equals { key, value } => format!("{key}={value}"),
+        Query::Lt { key, value } => format!("<{key}={value}"),
+        Query::Gt { key, value } => format!(">{key}={value}"),
+        Query::Operations(operations) => format!("#{operations}"),
+    }
+}
+
+#[async_trait]
+impl Client for RestClient {
+    async fn list<Resource>(&mut self) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.parse_records(Self::send(self.request(Method::GET, self.url::<Resource>(None))).await?)
+    }
+
+    async fn list_where<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.print(filter, None).await
+    }
+
+    async fn list_projected<Resource>(
+        &mut self,
+        filter: &Filter<Resource>,
+        projection: &Projection<Resource>,
+    ) -> Result<Vec<Resource>, RosError>
+    where
+        Resource: RouterOsResource,
+    {
+        self.print(filter, Some(projection)).await
+    }
+
+    async fn update<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        if let Some(id) = Self::id_of(&resource) {
+            let body = self.modified_fields(&resource)?;
+            if !body.is_empty() {
+                Self::send(
+                    self.request(Method::PATCH, self.url::<Resource>(Some(&id)))
+                        .json(&body),
+                )
+                .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn set<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsSingleResource,
+    {
+        let body = self.modified_fields(&resource)?;
+        if !body.is_empty() {
+            Self::send(
+                self.request(Method::POST, self.url::<Resource>(Some("set")))
+                    .json(&body),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn add_before<Resource>(
+        &mut self,
+        resource: &mut Resource,
+        before: Option<&str>,
+    ) -> Result<Option<String>, RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        let mut body = self.modified_fields(resource)?;
+        if let Some(before) = before {
+            body.insert(
+                String::from("place-before"),
+                Value::String(before.to_owned()),
+            );
+        }
+        let created = Self::send(
+            self.request(Method::PUT, self.url::<Resource>(None))
+                .json(&body),
+        )
+        .await?;
+        let id = created
+            .get(".id")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        if let Some(id) = &id {
+            resource.set_created_id(id)?;
+        }
+        Ok(id)
+    }
+
+    async fn move_before<Resource>(
+        &mut self,
+        resource: Resource,
+        before: Option<&str>,
+    ) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        if let Some(id) = Self::id_of(&resource) {
+            let mut body = Map::new();
+            body.insert(String::from("numbers"), Value::String(id));
+            if let Some(before) = before {
+                body.insert(
+                    String::from("destination"),
+                    Value::String(before.to_owned()),
+                );
+            }
+            Self::send(
+                self.request(Method::POST, self.url::<Resource>(Some("move")))
+                    .json(&body),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    async fn delete<Resource>(&mut self, resource: Resource) -> Result<(), RosError>
+    where
+        Resource: RouterOsListResource,
+    {
+        if let Some(id) = Self::id_of(&resource) {
+            Self::send(self.request(Method::DELETE, self.url::<Resource>(Some(&id)))).await?;
+        }
+        Ok(())
+    }
+
+    async fn list_dynamic(&mut self, menu: &DynamicMenu) -> Result<Vec<DynamicResource>, RosError> {
+        let records =
+            match Self::send(self.request(Method::GET, self.menu_url(menu.path(), None))).await? {
+                Value::Array(records) => records,
+                Value::Null => Vec::new(),
+                record => vec![record],
+            };
+        let mut ret = Vec::with_capacity(records.len());
+        for record in records {
+            let record = match record {
+                Value::Object(record) => record,
+                other => {
+                    return Err(RosError::SimpleMessage(format!(
+                        "Unexpected rest response: {other}"
+                    )))
+                }
+            };
+            let mut resource = menu.create();
+            for (key, value) in record {
+                resource.set_from_api(&key, &json_to_api(value))?;
+            }
+            ret.push(resource);
+        }
+        Ok(ret)
+    }
+
+    async fn update_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        let body = Self::dynamic_fields(&resource);
+        if body.is_empty() {
+            return Ok(());
+        }
+        let path = resource.menu().path();
+        let request = match resource
+            .id_field()
+            .and_then(|(_, field)| field.original_value(&ValueFormat::Api))
+        {
+            Some(id) => self.request(Method::PATCH, self.menu_url(path, Some(&id))),
+            None => self.request(Method::POST, self.menu_url(path, Some("set"))),
+        };
+        Self::send(request.json(&body)).await?;
+        Ok(())
+    }
+
+    async fn add_dynamic(
+        &mut self,
+        resource: &mut DynamicResource,
+    ) -> Result<Option<String>, RosError> {
+        let body = Self::dynamic_fields(resource);
+        let created = Self::send(
+            self.request(Method::PUT, self.menu_url(resource.menu().path(), None))
+                .json(&body),
+        )
+        .await?;
+        let id = created
+            .get(".id")
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+        if let Some(id) = &id {
+            resource.set_created_id(id)?;
+        }
+        Ok(id)
+    }
+
+    async fn delete_dynamic(&mut self, resource: DynamicResource) -> Result<(), RosError> {
+        let path = resource.menu().path();
+        let id = match resource
+            .id_field()
+            .and_then(|(_, field)| field.original_value(&ValueFormat::Api))
+        {
+            Some(id) => id,
+            None => {
+                return Err(RosError::SimpleMessage(format!(
+                    "Entry of {path} has no key to remove it by"
+                )))
+            }
+        };
+        Self::send(self.request(Method::DELETE, self.menu_url(path, Some(&id)))).await?;
+        Ok(())
+    }
+
+    async fn execute(&mut self, command: &Command) -> Result<CommandOutput, RosError> {
+        let body: Map<String, Value> = command
+            .arguments(&ValueFormat::Api)
+            .map(|(key, value)| (key.to_owned(), Value::String(value.to_owned())))
+            .collect();
+        let response = Self::send(
+            self.request(Method::POST, self.menu_url(&command.path(), None))
+                .json(&body),
+        )
+        .await?;
+        let to_row = |record: Map<String, Value>| {
+            record
+                .into_iter()
+                .map(|(key, value)| (key, json_to_api(value)))
+                .collect()
+        };
+        Ok(match response {
+            Value::Array(records) => CommandOutput {
+                rows: records
+                    .into_iter()
+                    .filter_map(|record| match record {
+                        Value::Object(record) => Some(to_row(record)),
+                        _ => None,
+                    })
+                    .collect(),
+                ret: None,
+            },
+            Value::Object(mut record) if record.len() == 1 && record.contains_key("ret") => {
+                CommandOutput {
+                    rows: Vec::new(),
+                    ret: record.remove("ret").map(json_to_api),
+                }
+            }
+            Value::Object(record) => CommandOutput {
+                rows: vec![to_row(record)],
+                ret: None,
+            },
+            _ => CommandOutput::default(),
+        })
+    }
+
+    fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        self.parser.take_warnings()
+    }
+}
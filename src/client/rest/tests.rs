@@ -0,0 +1,156 @@
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::client::rest::RestClient;
+use crate::client::tls::TlsConfig;
+use crate::client::version::UnsupportedFields;
+use crate::client::Client;
+use crate::generated::interface::bridge::Bridge;
+use crate::model::version::RosVersion;
+use crate::RosError;
+
+/// Answers a single http request with `status` and `body`, returns the request line and body
+async fn serve(status: &'static str, body: &'static str) -> (RestClient, JoinHandle<String>) {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0; 4096];
+        let (head, length) = loop {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+            let text = String::from_utf8_lossy(&request).into_owned();
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length: "))
+                    .map(|length| length.parse::<usize>().unwrap())
+                    .unwrap_or(0);
+                break (end + 4, length);
+            }
+        };
+        while request.len() < head + length {
+            let read = stream.read(&mut buffer).await.unwrap();
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        let text = String::from_utf8(request).unwrap();
+        let request_line = text.lines().next().unwrap().to_owned();
+        format!("{request_line} {}", &text[head..])
+    });
+    let client = RestClient::with_base_url(
+        format!("http://{address}/rest/"),
+        String::from("admin"),
+        String::new(),
+        TlsConfig::accept_any(),
+    )
+    .unwrap();
+    (client, server)
+}
+
+#[test]
+fn parse_and_modify_bridge() {
+    let record = match json!({".id": "*1", "name": "bridge", "vlan-filtering": "false", "mtu": "auto"})
+    {
+        Value::Object(record) => record,
+        _ => unreachable!(),
+    };
+    let mut client = RestClient::new(
+        Ipv4Addr::LOCALHOST.into(),
+        String::from("admin"),
+        String::new(),
+        TlsConfig::accept_any(),
+    )
+    .unwrap()
+    .unsupported_fields(UnsupportedFields::Skip);
+    let mut bridge: Bridge = client.parse_resource(record).unwrap();
+    assert_eq!(RestClient::id_of(&bridge), Some(String::from("*1")));
+    assert!(client.modified_fields(&bridge).unwrap().is_empty());
+
+    bridge.vlan_filtering.set(true);
+    bridge.ingress_filtering.set(true);
+    let modified = client.modified_fields(&bridge).unwrap();
+    assert_eq!(modified.len(), 2);
+    assert_eq!(modified.get("vlan-filtering"), Some(&json!("true")));
+
+    client.versions.version = Some(RosVersion::new(6, 40, 0));
+    let modified = client.modified_fields(&bridge).unwrap();
+    assert_eq!(modified.len(), 1);
+    assert_eq!(modified.get("ingress-filtering"), None);
+}
+
+#[test]
+fn ipv6_targets_are_bracketed() {
+    let client = RestClient::new(
+        Ipv6Addr::LOCALHOST.into(),
+        String::from("admin"),
+        String::new(),
+        TlsConfig::accept_any(),
+    )
+    .unwrap();
+    assert_eq!(client.base_url, "https://[::1]/rest");
+}
+
+#[tokio::test]
+async fn entries_are_listed() {
+    let (mut client, server) = serve(
+        "200 OK",
+        r#"[{".id":"*1","name":"bridge1"},{".id":"*2","name":"bridge2"}]"#,
+    )
+    .await;
+    let bridges: Vec<Bridge> = client.list().await.unwrap();
+    assert_eq!(
+        server.await.unwrap(),
+        "GET /rest/interface/bridge HTTP/1.1 "
+    );
+    assert_eq!(
+        bridges
+            .iter()
+            .map(|bridge| bridge.name.get().clone().unwrap())
+            .collect::<Vec<_>>(),
+        ["bridge1", "bridge2"]
+    );
+}
+
+#[tokio::test]
+async fn json_errors_keep_message_and_detail() {
+    let (mut client, server) = serve(
+        "400 Bad Request",
+        r#"{"error":400,"message":"Bad Request","detail":"failure: already have interface with such name"}"#,
+    )
+    .await;
+    let mut bridge = Bridge::default();
+    bridge.name.set(String::from("bridge1"));
+    let error = client.add(&mut bridge).await.unwrap_err();
+    assert_eq!(
+        server.await.unwrap(),
+        r#"PUT /rest/interface/bridge HTTP/1.1 {"name":"bridge1"}"#
+    );
+    assert!(matches!(
+        error,
+        RosError::RestError { status: 400, message, detail }
+            if message == "Bad Request"
+                && detail == "failure: already have interface with such name"
+    ));
+}
+
+#[tokio::test]
+async fn errors_without_json_keep_the_text() {
+    let (mut client, server) = serve("401 Unauthorized", "<h1>Unauthorized</h1>\n").await;
+    let error = client.list::<Bridge>().await.unwrap_err();
+    server.await.unwrap();
+    assert!(matches!(
+        error,
+        RosError::RestError { status: 401, message, detail }
+            if message == "<h1>Unauthorized</h1>" && detail.is_empty()
+    ));
+}
@@ -23,10 +23,11 @@ pub trait ClientSupplier<ReadClient: Client, WriteClient: Client> {
 
 #[async_trait]
 pub trait RouterOsConfiguration: Send {
-    async fn apply<CS: ClientSupplier<RC, WC> + Sync + Send, RC: Client, WC: Client>(
-        &self,
-        client: &mut CS,
-    ) -> Result<(), RosError>;
+    async fn apply<CS, RC, WC>(&self, client: &mut CS) -> Result<(), RosError>
+    where
+        CS: ClientSupplier<RC, WC> + Sync + Send,
+        RC: Client,
+        WC: Client;
 }
 
 pub struct SingleConfigSupplier<C: Client> {
@@ -0,0 +1,27 @@
+//! Helpers shared by the unit tests of the clients and accessors
+
+use crate::client::config::ConfigClient;
+use crate::client::Client;
+use crate::generated::interface::bridge::Bridge;
+use crate::model::ros_value::RosFieldAccessor;
+use crate::model::ValueFormat;
+
+pub(crate) fn bridge(name: &str) -> Bridge {
+    let mut bridge = Bridge::default();
+    bridge.name.set(name);
+    bridge
+}
+
+pub(crate) fn name_of(bridge: &Bridge) -> String {
+    bridge.name.api_value(&ValueFormat::Api)
+}
+
+/// Client that stores a bridge for each of `names`, with an empty script
+pub(crate) async fn client_with(names: &[&str]) -> ConfigClient {
+    let mut client = ConfigClient::new();
+    for name in names {
+        client.add(&mut bridge(name)).await.unwrap();
+    }
+    client.dump_cmd();
+    client
+}
@@ -0,0 +1,231 @@
+use std::fmt::{Debug, Formatter};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{
+    ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider,
+};
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    ClientConfig, DigitallySignedStruct, Error, RootCertStore, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Default port of the `api-ssl` service
+pub const API_SSL_PORT: u16 = 8729;
+
+/// How the certificate presented by the `api-ssl` service is checked.
+///
+/// The router needs a certificate assigned to `/ip/service api-ssl`, the anonymous
+/// cipher suites RouterOS falls back to without one are not supported.
+#[derive(Clone)]
+pub enum CertificateVerification {
+    /// Validate the certificate chain against the given trust anchors
+    CaBundle(Arc<RootCertStore>),
+    /// Accept only certificates whose SHA-256 fingerprint is in the list
+    Fingerprints(Vec<[u8; 32]>),
+    /// Accept any certificate without checking it, e.g. the self-signed one RouterOS
+    /// generates. The connection is encrypted but not protected against impersonation.
+    AcceptAny,
+}
+
+impl Debug for CertificateVerification {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CertificateVerification::CaBundle(roots) => {
+                write!(f, "CaBundle({} roots)", roots.len())
+            }
+            CertificateVerification::Fingerprints(fingerprints) => {
+                write!(f, "Fingerprints({} entries)", fingerprints.len())
+            }
+            CertificateVerification::AcceptAny => f.write_str("AcceptAny"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    verification: CertificateVerification,
+    server_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn new(verification: CertificateVerification) -> TlsConfig {
+        TlsConfig {
+            verification,
+            server_name: None,
+        }
+    }
+    pub fn accept_any() -> TlsConfig {
+        Self::new(CertificateVerification::AcceptAny)
+    }
+    pub fn with_ca_pem(pem: &[u8]) -> Result<TlsConfig, RosError> {
+        let mut roots = RootCertStore::empty();
+        for certificate in CertificateDer::pem_slice_iter(pem) {
+            let certificate = certificate
+                .map_err(|e| RosError::SimpleMessage(format!("Cannot parse CA bundle: {e}")))?;
+            roots.add(certificate)?;
+        }
+        if roots.is_empty() {
+            return Err(RosError::from("CA bundle contains no certificate"));
+        }
+        Ok(Self::new(CertificateVerification::CaBundle(Arc::new(
+            roots,
+        ))))
+    }
+    pub fn with_ca_file<P: AsRef<Path>>(path: P) -> Result<TlsConfig, RosError> {
+        Self::with_ca_pem(&std::fs::read(path)?)
+    }
+    pub fn with_fingerprints(fingerprints: Vec<[u8; 32]>) -> TlsConfig {
+        Self::new(CertificateVerification::Fingerprints(fingerprints))
+    }
+    /// Pin a certificate by its SHA-256 fingerprint as shown by
+    /// `/certificate print detail`, the hex digits may be separated by `:`, `-` or spaces.
+    pub fn with_fingerprint_hex(fingerprint: &str) -> Result<TlsConfig, RosError> {
+        Ok(Self::with_fingerprints(vec![parse_fingerprint(
+            fingerprint,
+        )?]))
+    }
+    /// Name to verify the certificate against instead of the ip address of the router
+    pub fn server_name<S: Into<String>>(mut self, server_name: S) -> TlsConfig {
+        self.server_name = Some(server_name.into());
+        self
+    }
+
+    pub(crate) async fn connect(
+        &self,
+        target: IpAddr,
+        stream: TcpStream,
+    ) -> Result<TlsStream<TcpStream>, RosError> {
+        let server_name = match &self.server_name {
+            Some(name) => ServerName::try_from(name.clone())
+                .map_err(|e| RosError::SimpleMessage(format!("Invalid server name {name}: {e}")))?,
+            None => ServerName::IpAddress(target.into()),
+        };
+        Ok(TlsConnector::from(Arc::new(self.client_config()?))
+            .connect(server_name, stream)
+            .await?)
+    }
+
+    pub(crate) fn client_config(&self) -> Result<ClientConfig, RosError> {
+        let provider = Arc::new(ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()?;
+        let config = match &self.verification {
+            CertificateVerification::CaBundle(roots) => builder
+                .with_root_certificates(roots.clone())
+                .with_no_client_auth(),
+            CertificateVerification::Fingerprints(fingerprints) => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerifier {
+                    fingerprints: Some(fingerprints.clone()),
+                    provider,
+                }))
+                .with_no_client_auth(),
+            CertificateVerification::AcceptAny => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedCertificateVerifier {
+                    fingerprints: None,
+                    provider,
+                }))
+                .with_no_client_auth(),
+        };
+        Ok(config)
+    }
+}
+
+fn parse_fingerprint(fingerprint: &str) -> Result<[u8; 32], RosError> {
+    let invalid = || RosError::SimpleMessage(format!("Invalid SHA-256 fingerprint: {fingerprint}"));
+    let mut digits: Vec<u32> = Vec::with_capacity(64);
+    for c in fingerprint.chars() {
+        match c.to_digit(16) {
+            Some(digit) => digits.push(digit),
+            None if c == ':' || c == '-' || c.is_whitespace() => {}
+            None => return Err(invalid()),
+        }
+    }
+    if digits.len() != 64 {
+        return Err(invalid());
+    }
+    let mut ret = [0u8; 32];
+    for (idx, pair) in digits.chunks(2).enumerate() {
+        ret[idx] = (pair[0] * 16 + pair[1]) as u8;
+    }
+    Ok(ret)
+}
+
+/// Verifies handshake signatures but skips the chain validation, optionally restricted to
+/// a set of certificate fingerprints
+#[derive(Debug)]
+struct PinnedCertificateVerifier {
+    fingerprints: Option<Vec<[u8; 32]>>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl ServerCertVerifier for PinnedCertificateVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, Error> {
+        if let Some(fingerprints) = &self.fingerprints {
+            let fingerprint: [u8; 32] = Sha256::digest(end_entity.as_ref()).into();
+            if !fingerprints.contains(&fingerprint) {
+                return Err(Error::General(String::from(
+                    "Certificate fingerprint does not match",
+                )));
+            }
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
@@ -0,0 +1,280 @@
+//! Commits of several resource accesses in an order the router accepts.
+//!
+//! Entries refer to entries of other menus, e.g. a bridge port to its bridge or an address
+//! to its vlan interface. A [`Transaction`] removes entries in reverse dependency order,
+//! the addresses before the vlan interfaces before the bridges, and updates and adds them
+//! in dependency order. The dependencies are the references declared with `->` in
+//! `ros_model`, further ones can be added with [`Transaction::depends_on`]. Accesses of menus
+//! without dependency between them are committed in the order they were registered.
+//!
+//! ```ignore
+//! let mut bridges = client.fetch::<Bridge>().await?;
+//! let mut ports = client.fetch::<BridgePort>().await?;
+//! let mut addresses = client.fetch::<Address>().await?;
+//! // ...
+//! let mut transaction = Transaction::new();
+//! transaction
+//!     .register(&mut addresses)
+//!     .register(&mut ports)
+//!     .register(&mut bridges);
+//! transaction.commit(&mut client).await?;
+//! ```
+
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+
+use crate::client::journal::JournalingClient;
+use crate::client::plan::{ChangePlan, PlannedChange};
+use crate::client::{Client, ResourceAccess};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Client a [`TransactionStep`] sends its changes with
+pub enum StepClient<'s, 'c, C: Client> {
+    /// Changes are sent as they are
+    Plain(&'s mut C),
+    /// Changes are recorded to be undone if a later step fails
+    Journaling(&'s mut JournalingClient<'c, C>),
+}
+
+impl<'c, C: Client> StepClient<'_, 'c, C> {
+    fn reborrow(&mut self) -> StepClient<'_, 'c, C> {
+        match self {
+            StepClient::Plain(client) => StepClient::Plain(client),
+            StepClient::Journaling(client) => StepClient::Journaling(client),
+        }
+    }
+}
+
+/// Resource access as part of a [`Transaction`], implemented for every [`ResourceAccess`]
+#[async_trait]
+pub trait TransactionStep<C: Client>: Send {
+    fn resource_path(&self) -> &'static str;
+    fn referenced_menus(&self) -> Vec<&'static str>;
+    fn plan(&self) -> ChangePlan;
+    async fn commit_remove(&mut self, client: StepClient<'_, '_, C>) -> Result<(), RosError>;
+    async fn commit_update(&mut self, client: StepClient<'_, '_, C>) -> Result<(), RosError>;
+    async fn commit_add(&mut self, client: StepClient<'_, '_, C>) -> Result<(), RosError>;
+    async fn rollback(&mut self, client: &mut C) -> Result<(), RosError>;
+}
+
+#[async_trait]
+impl<C, A> TransactionStep<C> for A
+where
+    C: Client,
+    A: ResourceAccess + Send,
+{
+    fn resource_path(&self) -> &'static str {
+        ResourceAccess::resource_path(self)
+    }
+    fn referenced_menus(&self) -> Vec<&'static str> {
+        ResourceAccess::referenced_menus(self)
+    }
+    fn plan(&self) -> ChangePlan {
+        ResourceAccess::plan(self)
+    }
+    async fn commit_remove(&mut self, client: StepClient<'_, '_, C>) -> Result<(), RosError> {
+        match client {
+            StepClient::Plain(client) => ResourceAccess::commit_remove(self, client).await,
+            StepClient::Journaling(client) => ResourceAccess::commit_remove(self, client).await,
+        }
+    }
+    async fn commit_update(&mut self, client: StepClient<'_, '_, C>) -> Result<(), RosError> {
+        match client {
+            StepClient::Plain(client) => ResourceAccess::commit_update(self, client).await,
+            StepClient::Journaling(client) => ResourceAccess::commit_update(self, client).await,
+        }
+    }
+    async fn commit_add(&mut self, client: StepClient<'_, '_, C>) -> Result<(), RosError> {
+        match client {
+            StepClient::Plain(client) => ResourceAccess::commit_add(self, client).await,
+            StepClient::Journaling(client) => ResourceAccess::commit_add(self, client).await,
+        }
+    }
+    async fn rollback(&mut self, client: &mut C) -> Result<(), RosError> {
+        ResourceAccess::rollback(self, client).await
+    }
+}
+
+/// Group of resource accesses committed together, see the module documentation
+pub struct Transaction<'a, C: Client> {
+    steps: Vec<&'a mut dyn TransactionStep<C>>,
+    dependencies: Vec<(String, String)>,
+}
+
+impl<C: Client> Default for Transaction<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, C: Client> Transaction<'a, C> {
+    /// Empty transaction, the menus of the registered accesses are ordered by the references
+    /// of their fields
+    pub fn new() -> Transaction<'a, C> {
+        Transaction {
+            steps: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+    pub fn register<A>(&mut self, access: &'a mut A) -> &mut Self
+    where
+        A: TransactionStep<C>,
+    {
+        self.steps.push(access);
+        self
+    }
+    /// Declares that entries of `menu` refer to entries of `dependency` in addition to the
+    /// references of the model, e.g. `depends_on("ip/firewall/filter", "interface/list")`
+    pub fn depends_on(&mut self, menu: &str, dependency: &str) -> &mut Self {
+        self.dependencies.push((
+            menu.trim_matches('/').to_owned(),
+            dependency.trim_matches('/').to_owned(),
+        ));
+        self
+    }
+
+    /// Declared dependencies and the references of the registered accesses
+    fn dependencies(&self) -> Vec<(&str, &str)> {
+        self.dependencies
+            .iter()
+            .map(|(menu, dependency)| (menu.as_str(), dependency.as_str()))
+            .chain(self.steps.iter().flat_map(|step| {
+                step.referenced_menus()
+                    .into_iter()
+                    .map(|dependency| (step.resource_path(), dependency))
+            }))
+            .collect()
+    }
+
+    /// Whether `menu` depends on `dependency`, directly or through other menus
+    fn depends(dependencies: &[(&str, &str)], menu: &str, dependency: &str) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![menu];
+        while let Some(current) = pending.pop() {
+            for (dependent, target) in dependencies {
+                if *dependent == current && visited.insert(*target) {
+                    if *target == dependency {
+                        return true;
+                    }
+                    pending.push(target);
+                }
+            }
+        }
+        false
+    }
+
+    /// Indices of the steps in dependency order, registration order where it does not
+    /// matter
+    fn order(&self) -> Result<Vec<usize>, RosError> {
+        let paths: Vec<&str> = self.steps.iter().map(|step| step.resource_path()).collect();
+        let dependencies = self.dependencies();
+        let mut ret = Vec::with_capacity(paths.len());
+        let mut pending: Vec<usize> = (0..paths.len()).collect();
+        while !pending.is_empty() {
+            let ready = pending.iter().position(|candidate| {
+                pending.iter().all(|other| {
+                    paths[*other] == paths[*candidate]
+                        || !Self::depends(&dependencies, paths[*candidate], paths[*other])
+                })
+            });
+            match ready {
+                Some(position) => ret.push(pending.remove(position)),
+                None => {
+                    let menus: Vec<&str> = pending.iter().map(|index| paths[*index]).collect();
+                    return Err(RosError::SimpleMessage(format!(
+                        "Cyclic dependency between {}",
+                        menus.join(", ")
+                    )));
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Menus of the registered accesses in the order adds and updates are sent
+    pub fn menus(&self) -> Result<Vec<&'static str>, RosError> {
+        Ok(self
+            .order()?
+            .into_iter()
+            .map(|index| self.steps[index].resource_path())
+            .collect())
+    }
+
+    /// Changes [`Transaction::commit`] would send, in the order it sends them
+    pub fn plan(&self) -> Result<ChangePlan, RosError> {
+        let order = self.order()?;
+        let plans: Vec<ChangePlan> = self.steps.iter().map(|step| step.plan()).collect();
+        let mut ret = ChangePlan::new();
+        for index in order.iter().rev() {
+            ret.extend(
+                plans[*index]
+                    .changes()
+                    .iter()
+                    .filter(|change| matches!(change, PlannedChange::Delete { .. }))
+                    .cloned(),
+            );
+        }
+        for index in &order {
+            ret.extend(
+                plans[*index]
+                    .changes()
+                    .iter()
+                    .filter(|change| !matches!(change, PlannedChange::Delete { .. }))
+                    .cloned(),
+            );
+        }
+        Ok(ret)
+    }
+
+    async fn run(
+        &mut self,
+        order: &[usize],
+        mut client: StepClient<'_, '_, C>,
+    ) -> Result<(), RosError> {
+        for index in order.iter().rev() {
+            self.steps[*index].commit_remove(client.reborrow()).await?;
+        }
+        for index in order {
+            self.steps[*index].commit_update(client.reborrow()).await?;
+            self.steps[*index].commit_add(client.reborrow()).await?;
+        }
+        Ok(())
+    }
+
+    /// Reloads the entries of all registered accesses
+    pub async fn rollback(&mut self, client: &mut C) -> Result<(), RosError> {
+        for step in self.steps.iter_mut() {
+            step.rollback(client).await?;
+        }
+        Ok(())
+    }
+
+    /// Commits all registered accesses and reloads their entries
+    pub async fn commit(&mut self, client: &mut C) -> Result<(), RosError> {
+        let order = self.order()?;
+        self.run(&order, StepClient::Plain(client)).await?;
+        self.rollback(client).await
+    }
+
+    /// Commits like [`Transaction::commit`], if a step fails the changes already sent to
+    /// any of the menus are undone, see [`crate::client::ResourceAccess::commit_transactional`]
+    pub async fn commit_transactional(&mut self, client: &mut C) -> Result<(), RosError> {
+        let order = self.order()?;
+        let mut journal = JournalingClient::new(client);
+        match self.run(&order, StepClient::Journaling(&mut journal)).await {
+            Ok(()) => self.rollback(client).await,
+            Err(error) => {
+                let report = journal.undo().await;
+                let _ = self.rollback(client).await;
+                Err(RosError::RolledBack {
+                    error: Box::new(error),
+                    report: Box::new(report),
+                })
+            }
+        }
+    }
+}
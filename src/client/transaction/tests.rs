@@ -0,0 +1,101 @@
+use ipnet::IpNet;
+
+use crate::client::config::ConfigClient;
+use crate::client::transaction::Transaction;
+use crate::client::Client;
+use crate::generated::interface::bridge::port::BridgePort;
+use crate::generated::interface::bridge::Bridge;
+use crate::generated::interface::vlan::Vlan;
+use crate::generated::ip::address::Address;
+use crate::generated::ip::route::Route;
+use crate::RosError;
+
+/// Context lines of the script, one per menu change
+fn menus(script: &str) -> Vec<&str> {
+    script
+        .lines()
+        .filter(|line| line.starts_with('/'))
+        .collect()
+}
+
+#[tokio::test]
+async fn adds_follow_and_removals_precede_dependencies() {
+    let mut client = ConfigClient::new();
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    let mut ports = client.fetch::<BridgePort>().await.unwrap();
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    let address: IpNet = "10.0.0.1/24".parse().unwrap();
+    addresses.get_or_default(|_| false).address.set(address);
+    ports.get_or_default(|_| false).bridge.set("bridge");
+    bridges.get_or_default(|_| false).name.set("bridge");
+
+    let mut transaction = Transaction::new();
+    transaction
+        .register(&mut addresses)
+        .register(&mut ports)
+        .register(&mut bridges);
+    assert_eq!(
+        transaction.menus().unwrap(),
+        ["interface/bridge", "ip/address", "interface/bridge/port"]
+    );
+    assert_eq!(transaction.plan().unwrap().len(), 3);
+    transaction.commit(&mut client).await.unwrap();
+    assert_eq!(
+        menus(&client.dump_cmd()),
+        ["/interface bridge", "/ip address", "/interface bridge port"]
+    );
+
+    // scripts cannot remove ports and addresses, they have no key besides .id
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    let mut ports = client.fetch::<BridgePort>().await.unwrap();
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    addresses.remove(|_| true);
+    ports.remove(|_| true);
+    bridges.remove(|_| true);
+    let mut transaction: Transaction<ConfigClient> = Transaction::new();
+    transaction
+        .register(&mut bridges)
+        .register(&mut addresses)
+        .register(&mut ports);
+    let plan = transaction.plan().unwrap();
+    let paths: Vec<&str> = plan.changes().iter().map(|change| change.path()).collect();
+    assert_eq!(
+        paths,
+        ["interface/bridge/port", "ip/address", "interface/bridge"]
+    );
+}
+
+#[tokio::test]
+async fn dependencies_follow_the_references_of_the_model() {
+    let mut client = ConfigClient::new();
+    let mut routes = client.fetch::<Route>().await.unwrap();
+    let mut vlans = client.fetch::<Vlan>().await.unwrap();
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    let mut transaction: Transaction<ConfigClient> = Transaction::new();
+    transaction
+        .register(&mut routes)
+        .register(&mut vlans)
+        .register(&mut bridges);
+    assert_eq!(
+        transaction.menus().unwrap(),
+        ["interface/bridge", "interface/vlan", "ip/route"]
+    );
+}
+
+#[tokio::test]
+async fn cyclic_dependencies_are_rejected() {
+    let mut client = ConfigClient::new();
+    let mut bridges = client.fetch::<Bridge>().await.unwrap();
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    let mut transaction = Transaction::new();
+    transaction
+        .register(&mut bridges)
+        .register(&mut addresses)
+        .depends_on("/interface/bridge", "/ip/address");
+    let error = transaction.commit(&mut client).await.unwrap_err();
+    assert!(matches!(
+        error,
+        RosError::SimpleMessage(message)
+            if message == "Cyclic dependency between interface/bridge, ip/address"
+    ));
+}
@@ -0,0 +1,75 @@
+//! Fields that only exist in some RouterOS releases.
+//!
+//! Clients that know the version of the router, see [`router_version`], do not send
+//! modified fields the version does not support. Depending on [`UnsupportedFields`] they
+//! fail before sending anything or leave these fields out.
+
+use crate::client::filter::{Filter, Projection};
+use crate::client::Client;
+use crate::field_ref;
+use crate::generated::system::resource::Resource;
+use crate::model::version::RosVersion;
+use crate::model::{RouterOsResource, ValueFormat};
+use crate::RosError;
+
+/// What a client does with modified fields the router version does not support
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum UnsupportedFields {
+    /// Fails with [`RosError::UnsupportedField`]
+    #[default]
+    Reject,
+    /// Sends the other fields only
+    Skip,
+}
+
+/// Version of the router a client writes to
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VersionFilter {
+    pub(crate) version: Option<RosVersion>,
+    pub(crate) unsupported: UnsupportedFields,
+}
+
+impl VersionFilter {
+    /// Modified values of `resource` that can be sent, all of them if the version is unknown,
+    /// followed by the modified [`ExtraAttributes`](crate::model::extra::ExtraAttributes)
+    pub(crate) fn modified_values<'r, R: RouterOsResource>(
+        &self,
+        resource: &'r R,
+        format: &ValueFormat,
+    ) -> Result<Vec<(&'r str, String)>, RosError> {
+        let mut ret = Vec::new();
+        for (description, field) in resource.fields() {
+            let value = match field.modified_value(format) {
+                Some(value) => value,
+                None => continue,
+            };
+            match &self.version {
+                Some(version) if !description.is_supported_by(version) => {
+                    if self.unsupported == UnsupportedFields::Reject {
+                        return Err(RosError::UnsupportedField {
+                            structure: R::resource_path(),
+                            field_name: description.name,
+                            version: *version,
+                        });
+                    }
+                }
+                _ => ret.push((description.name, value)),
+            }
+        }
+        if let Some(extra) = resource.extra_attributes() {
+            ret.extend(extra.modified_values());
+        }
+        Ok(ret)
+    }
+}
+
+/// Version of the router `client` is connected to, `None` if it reports none
+pub async fn router_version<C: Client>(client: &mut C) -> Result<Option<RosVersion>, RosError> {
+    let projection = Projection::new().field(&field_ref!(Resource => version));
+    let resources: Vec<Resource> = client.list_projected(&Filter::new(), &projection).await?;
+    resources
+        .first()
+        .and_then(|resource| resource.version.get().as_deref())
+        .map(str::parse)
+        .transpose()
+}
@@ -1,10 +1,9 @@
 use std::ops::DerefMut;
 
-use field_ref::field_ref_of;
-
 use crate::client::config::ConfigClient;
+use crate::client::transaction::Transaction;
 use crate::client::Client;
-use crate::client::ResourceAccess;
+use crate::field_ref;
 use crate::generated::interface::ethernet::Ethernet;
 use crate::generated::interface::wireless::Wireless;
 use crate::generated::system::resource::Resource;
@@ -50,17 +49,20 @@ impl MikrotikModel {
         let mut wlan = client.fetch::<Wireless>().await?;
         let mut resource = client.get::<Resource>().await?;
         for if_name in self.ethernet_interface_names() {
-            eth.get_or_create_by_value(&field_ref_of!(Ethernet => default_name), if_name);
+            eth.get_or_create_by_value(&field_ref!(Ethernet => default_name), if_name);
         }
         for if_name in self.wireless_interface_names() {
-            wlan.get_or_create_by_value(&field_ref_of!(Wireless => default_name), if_name);
+            wlan.get_or_create_by_value(&field_ref!(Wireless => default_name), if_name);
         }
 
         resource.deref_mut().board_name.set(self.board_name());
 
-        wlan.commit(client).await?;
-        eth.commit(client).await?;
-        resource.commit(client).await?;
+        Transaction::new()
+            .register(&mut wlan)
+            .register(&mut eth)
+            .register(&mut resource)
+            .commit(client)
+            .await?;
         client.dump_cmd();
         Ok(())
     }
@@ -1,18 +1,48 @@
 use core::convert::{From, Infallible};
 use core::fmt::{Display, Formatter};
+use std::collections::HashMap;
 use std::net::AddrParseError;
 use std::num::ParseIntError;
 use std::str::ParseBoolError;
 
 use mac_address::MacParseError;
 
+use crate::client::api::{LoginScheme, TrapCategory};
+use crate::client::journal::CompensationReport;
+use crate::model::version::RosVersion;
 use crate::RosError::FieldMissingError;
 
+extern crate self as routeros;
+
 pub mod client;
 pub mod hardware;
 pub mod model;
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
 
+/// Creates a `field_ref::FieldRef` to a field of a resource, e.g.
+/// `field_ref!(Route => dst_address)`.
+///
+/// Replaces `field_ref::field_ref_of!`, which dereferences a null pointer and aborts in
+/// debug builds of current compilers.
+#[macro_export]
+macro_rules! field_ref {
+    ($resource:ty => $field:ident) => {{
+        fn typed<T, U>(
+            field_ref: ::field_ref::FieldRef<T, U>,
+            _: fn(&T) -> &U,
+        ) -> ::field_ref::FieldRef<T, U> {
+            field_ref
+        }
+        typed(
+            // SAFETY: the offset is the one of `$field` and `typed` checks the field type
+            unsafe {
+                ::field_ref::FieldRef::from_offset(::std::mem::offset_of!($resource, $field))
+            },
+            |resource: &$resource| &resource.$field,
+        )
+    }};
+}
+
 #[derive(Debug)]
 pub enum RosError {
     TokioError(tokio::io::Error),
@@ -22,6 +52,35 @@ pub enum RosError {
     AddrParseError(AddrParseError),
     IpNetAddrParseError(ipnet::AddrParseError),
     MacParseError(MacParseError),
+    TlsError(tokio_rustls::rustls::Error),
+    AuthenticationError {
+        scheme: LoginScheme,
+        message: String,
+    },
+    HttpError(reqwest::Error),
+    JsonError(serde_json::Error),
+    RestError {
+        status: u16,
+        message: String,
+        detail: String,
+    },
+    /// Word received from the router that does not follow the api protocol
+    ProtocolError {
+        reason: &'static str,
+        word: Vec<u8>,
+    },
+    /// `!trap` reply of the router to `command`
+    Trap {
+        category: Option<TrapCategory>,
+        message: String,
+        command: String,
+        tag: Option<String>,
+        attributes: Box<HashMap<String, String>>,
+    },
+    /// `!fatal` reply, the router closed the connection
+    Fatal {
+        message: String,
+    },
     Umbrella(Vec<RosError>),
     FieldWriteError {
         field_name: String,
@@ -36,9 +95,41 @@ pub enum RosError {
         structure: &'static str,
         error: Box<RosError>,
     },
+    /// Modified field the RouterOS `version` of the router does not know
+    UnsupportedField {
+        structure: &'static str,
+        field_name: &'static str,
+        version: RosVersion,
+    },
+    /// Transactional commit that failed with `error`, the changes already sent were undone
+    /// as far as `report` says
+    RolledBack {
+        error: Box<RosError>,
+        report: Box<CompensationReport>,
+    },
 }
 
 impl RosError {
+    pub fn protocol_error(reason: &'static str, word: &[u8]) -> RosError {
+        RosError::ProtocolError {
+            reason,
+            word: word.to_vec(),
+        }
+    }
+    /// Whether the error means the connection to the router is lost or unusable
+    pub fn is_connection_error(&self) -> bool {
+        matches!(
+            self,
+            RosError::TokioError(_) | RosError::TlsError(_) | RosError::Fatal { .. }
+        )
+    }
+    /// Category of a `!trap` error
+    pub fn trap_category(&self) -> Option<TrapCategory> {
+        match self {
+            RosError::Trap { category, .. } => *category,
+            _ => None,
+        }
+    }
     pub fn field_missing_error<K: ToString, V: ToString>(
         field_name: K,
         field_value: V,
@@ -60,6 +151,38 @@ impl Display for RosError {
             RosError::AddrParseError(e) => std::fmt::Display::fmt(&e, f),
             RosError::IpNetAddrParseError(e) => std::fmt::Display::fmt(&e, f),
             RosError::MacParseError(e) => std::fmt::Display::fmt(&e, f),
+            RosError::TlsError(e) => std::fmt::Display::fmt(&e, f),
+            RosError::AuthenticationError { scheme, message } => {
+                write!(f, "Login failed ({scheme:?}): {message}")
+            }
+            RosError::HttpError(e) => std::fmt::Display::fmt(&e, f),
+            RosError::JsonError(e) => std::fmt::Display::fmt(&e, f),
+            RosError::RestError {
+                status,
+                message,
+                detail,
+            } => {
+                write!(f, "Rest call failed with status {status}: {message}")?;
+                if !detail.is_empty() {
+                    f.write_str(" (")?;
+                    f.write_str(detail)?;
+                    f.write_str(")")?;
+                }
+                Ok(())
+            }
+            RosError::ProtocolError { reason, word } => {
+                write!(f, "{reason}: {}", String::from_utf8_lossy(word))
+            }
+            RosError::Trap {
+                category,
+                message,
+                command,
+                ..
+            } => match category {
+                Some(category) => write!(f, "/{command} failed ({category:?}): {message}"),
+                None => write!(f, "/{command} failed: {message}"),
+            },
+            RosError::Fatal { message } => write!(f, "Connection closed by router: {message}"),
             RosError::Umbrella(errors) => {
                 for error in errors {
                     std::fmt::Display::fmt(&error, f)?;
@@ -72,9 +195,9 @@ impl Display for RosError {
                 error,
             } => {
                 f.write_str("Error on field on ")?;
-                f.write_str(&field_name)?;
+                f.write_str(field_name)?;
                 f.write_str(" value ")?;
-                f.write_str(&field_value)?;
+                f.write_str(field_value)?;
                 f.write_str(": ")?;
                 std::fmt::Display::fmt(&error, f)?;
                 Ok(())
@@ -84,10 +207,10 @@ impl Display for RosError {
                 field_value,
             } => {
                 f.write_str("Missing field ")?;
-                f.write_str(&field_name)?;
+                f.write_str(field_name)?;
                 f.write_str(": ")?;
                 f.write_str(" value from api ")?;
-                f.write_str(&field_value)
+                f.write_str(field_value)
             }
             RosError::StructureAccessError { structure, error } => {
                 f.write_str("Error on structure ")?;
@@ -96,6 +219,15 @@ impl Display for RosError {
                 std::fmt::Display::fmt(&error, f)?;
                 Ok(())
             }
+            RosError::UnsupportedField {
+                structure,
+                field_name,
+                version,
+            } => write!(
+                f,
+                "Field {field_name} of {structure} is not supported by RouterOS {version}"
+            ),
+            RosError::RolledBack { error, report } => write!(f, "{error}, {report}"),
         }
     }
 }
@@ -130,6 +262,24 @@ impl From<MacParseError> for RosError {
     }
 }
 
+impl From<tokio_rustls::rustls::Error> for RosError {
+    fn from(e: tokio_rustls::rustls::Error) -> Self {
+        RosError::TlsError(e)
+    }
+}
+
+impl From<reqwest::Error> for RosError {
+    fn from(e: reqwest::Error) -> Self {
+        RosError::HttpError(e)
+    }
+}
+
+impl From<serde_json::Error> for RosError {
+    fn from(e: serde_json::Error) -> Self {
+        RosError::JsonError(e)
+    }
+}
+
 impl From<tokio::io::Error> for RosError {
     fn from(e: tokio::io::Error) -> Self {
         RosError::TokioError(e)
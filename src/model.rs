@@ -2,12 +2,18 @@ use std::fmt::{Debug, Display, Formatter};
 use std::net::IpAddr;
 use std::ops::{Deref, DerefMut};
 
+use crate::model::extra::ExtraAttributes;
 pub use crate::model::ros_value::{RosFieldAccessor, RosValue, ValueFormat};
+use crate::model::version::RosVersion;
 use crate::RosError;
+pub use ros_macro::RouterOsResource;
 
+pub mod dynamic;
+pub mod extra;
 pub mod inet;
 
 pub mod ros_value;
+pub mod version;
 
 #[derive(Debug, Clone)]
 pub struct RosFieldValue<T>
@@ -68,10 +74,7 @@ where
         if self.original_value.is_empty() {
             Option::None
         } else {
-            match T::from_api(&self.original_value) {
-                Ok(value) => Option::Some(value),
-                Err(_) => Option::None,
-            }
+            T::from_api(&self.original_value).ok()
         }
     }
 }
@@ -110,6 +113,15 @@ where
         self.reset()
     }
 
+    fn set_api_value(&mut self, value: &str) -> Result<(), RosError> {
+        self.current_value = if value.is_empty() {
+            None
+        } else {
+            Some(T::from_api(value).map_err(|e| e.into())?)
+        };
+        Ok(())
+    }
+
     fn clear(&mut self) -> Result<(), RosError> {
         self.current_value = None;
         Ok(())
@@ -166,6 +178,28 @@ pub struct FieldDescription {
     pub name: &'static str,
     pub is_read_only: bool,
     pub is_id: bool,
+    /// Key fields of other menus the values refer to, declared with `->` in `ros_model`
+    pub references: &'static [FieldReference],
+    /// First RouterOS version with the field, declared with `@7..` in `ros_model`
+    pub since: Option<RosVersion>,
+    /// First RouterOS version without the field, declared with `@..7` in `ros_model`
+    pub until: Option<RosVersion>,
+}
+
+impl FieldDescription {
+    /// Whether routers running `version` know the field
+    pub fn is_supported_by(&self, version: &RosVersion) -> bool {
+        self.since.is_none_or(|since| since <= *version)
+            && self.until.is_none_or(|until| *version < until)
+    }
+}
+
+/// Field of a menu another field refers to, e.g. `name` of `interface/bridge` for `bridge`
+/// of `interface/bridge/port`
+#[derive(Debug, Eq, PartialEq)]
+pub struct FieldReference {
+    pub path: &'static str,
+    pub field: &'static str,
 }
 
 pub trait RouterOsApiFieldAccess {
@@ -178,6 +212,13 @@ pub trait RouterOsApiFieldAccess {
     fn is_dynamic(&self) -> bool {
         false
     }
+    /// Attributes that did not fit the fields, for resources that keep them
+    fn extra_attributes(&self) -> Option<&ExtraAttributes> {
+        None
+    }
+    fn extra_attributes_mut(&mut self) -> Option<&mut ExtraAttributes> {
+        None
+    }
 }
 
 pub trait RouterOsResource:
@@ -191,12 +232,24 @@ pub trait RouterOsResource:
     fn is_modified(&self) -> bool {
         return self
             .fields()
-            .any(|e| e.1.modified_value(&ValueFormat::Api).is_some());
+            .any(|e| e.1.modified_value(&ValueFormat::Api).is_some())
+            || self
+                .extra_attributes()
+                .is_some_and(ExtraAttributes::is_modified);
     }
     fn id_field(&self) -> Option<(&'static FieldDescription, &dyn RosFieldAccessor)> {
         self.fields()
-            .filter(|(description, value)| description.is_id && value.has_value())
-            .next()
+            .find(|(description, value)| description.is_id && value.has_value())
+    }
+    /// Stores the `.id` the router assigned to a newly added entry
+    fn set_created_id(&mut self, id: &str) -> Result<(), RosError> {
+        if let Some((_, field)) = self
+            .fields_mut()
+            .find(|(description, _)| description.name == ".id")
+        {
+            field.set_from_api(id)?;
+        }
+        Ok(())
     }
 }
 
@@ -0,0 +1,143 @@
+use crate::model::{RosFieldAccessor, RosFieldValue, ValueFormat};
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// Menu that is not part of the generated model, e.g. `ip/firewall/raw`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DynamicMenu {
+    path: String,
+    key_fields: Vec<String>,
+}
+
+impl DynamicMenu {
+    /// Menu at `path`, its entries are identified by `.id`
+    pub fn new<P: Into<String>>(path: P) -> DynamicMenu {
+        DynamicMenu {
+            path: path.into().trim_matches('/').to_owned(),
+            key_fields: vec![String::from(".id")],
+        }
+    }
+    /// Adds a field that identifies entries, e.g. `name`, like `*` in `ros_model`
+    pub fn key_field<F: Into<String>>(mut self, field: F) -> DynamicMenu {
+        let field = field.into();
+        if !self.key_fields.contains(&field) {
+            self.key_fields.push(field);
+        }
+        self
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    pub fn key_fields(&self) -> &[String] {
+        &self.key_fields
+    }
+    pub fn is_key(&self, field: &str) -> bool {
+        self.key_fields.iter().any(|key| key == field)
+    }
+
+    /// New entry of the menu without attributes
+    pub fn create(&self) -> DynamicResource {
+        DynamicResource {
+            menu: self.clone(),
+            fields: Vec::new(),
+        }
+    }
+}
+
+/// Entry of a [`DynamicMenu`], the attributes are kept as text in the order they were
+/// read or set.
+///
+/// Values track their original value like the fields of generated resources, only
+/// modified ones are sent on update.
+#[derive(Debug, Clone)]
+pub struct DynamicResource {
+    menu: DynamicMenu,
+    fields: Vec<(String, RosFieldValue<String>)>,
+}
+
+impl DynamicResource {
+    pub fn menu(&self) -> &DynamicMenu {
+        &self.menu
+    }
+
+    /// Current value of `field`, `None` if it has none
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.field(field).and_then(|value| value.get().as_deref())
+    }
+    pub fn set<F, V>(&mut self, field: F, value: V)
+    where
+        F: AsRef<str>,
+        V: Into<String>,
+    {
+        self.field_or_insert(field.as_ref()).set(value.into());
+    }
+    pub fn clear(&mut self, field: &str) {
+        if let Some((_, value)) = self.fields.iter_mut().find(|(name, _)| name == field) {
+            value.clear();
+        }
+    }
+
+    /// Names and values of the attributes in order
+    pub fn fields(&self) -> impl Iterator<Item = (&str, &dyn RosFieldAccessor)> + '_ {
+        self.fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), value as &dyn RosFieldAccessor))
+    }
+    pub fn fields_mut(&mut self) -> impl Iterator<Item = (&str, &mut dyn RosFieldAccessor)> + '_ {
+        self.fields
+            .iter_mut()
+            .map(|(name, value)| (name.as_str(), value as &mut dyn RosFieldAccessor))
+    }
+
+    /// Stores a value read from the router as original value of `field`
+    pub fn set_from_api(&mut self, field: &str, value: &str) -> Result<(), RosError> {
+        RosFieldAccessor::set_from_api(self.field_or_insert(field), value)
+    }
+
+    /// Modified attributes with their new value
+    pub fn modified_values(&self, format: &ValueFormat) -> Vec<(&str, String)> {
+        self.fields()
+            .filter_map(|(name, field)| field.modified_value(format).map(|value| (name, value)))
+            .collect()
+    }
+    pub fn is_modified(&self) -> bool {
+        self.fields()
+            .any(|(_, field)| field.modified_value(&ValueFormat::Api).is_some())
+    }
+    /// First key field of the menu with a value
+    pub fn id_field(&self) -> Option<(&str, &dyn RosFieldAccessor)> {
+        self.menu.key_fields.iter().find_map(|key| {
+            self.fields()
+                .find(|(name, field)| name == key && field.has_value())
+        })
+    }
+    /// Stores the `.id` the router assigned to a newly added entry
+    pub fn set_created_id(&mut self, id: &str) -> Result<(), RosError> {
+        self.set_from_api(".id", id)
+    }
+    /// Whether the router created the entry, see [`crate::model::RouterOsApiFieldAccess::is_dynamic`]
+    pub fn is_dynamic(&self) -> bool {
+        self.get("dynamic") == Some("true")
+    }
+
+    fn field(&self, field: &str) -> Option<&RosFieldValue<String>> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == field)
+            .map(|(_, value)| value)
+    }
+    fn field_or_insert(&mut self, field: &str) -> &mut RosFieldValue<String> {
+        let position = match self.fields.iter().position(|(name, _)| name == field) {
+            Some(position) => position,
+            None => {
+                self.fields
+                    .push((field.to_owned(), RosFieldValue::default()));
+                self.fields.len() - 1
+            }
+        };
+        &mut self.fields[position].1
+    }
+}
@@ -0,0 +1,42 @@
+use crate::model::dynamic::DynamicMenu;
+use crate::model::ValueFormat;
+
+#[test]
+fn tracks_modified_attributes_in_order() {
+    let menu = DynamicMenu::new("/tool/netwatch/");
+    assert_eq!(menu.path(), "tool/netwatch");
+    let mut entry = menu.create();
+    entry.set_from_api(".id", "*1").unwrap();
+    entry.set_from_api("host", "10.0.0.1").unwrap();
+    entry.set_from_api("comment", "uplink").unwrap();
+    assert!(!entry.is_modified());
+
+    entry.set("interval", "10s");
+    entry.set("host", "10.0.0.2");
+    entry.set("comment", "uplink");
+    entry.clear("comment");
+    assert_eq!(entry.get("host"), Some("10.0.0.2"));
+    assert_eq!(entry.get("comment"), None);
+    assert_eq!(
+        entry.modified_values(&ValueFormat::Api),
+        [
+            ("host", String::from("10.0.0.2")),
+            ("comment", String::new()),
+            ("interval", String::from("10s"))
+        ]
+    );
+    let names: Vec<&str> = entry.fields().map(|(name, _)| name).collect();
+    assert_eq!(names, [".id", "host", "comment", "interval"]);
+}
+
+#[test]
+fn key_fields_are_configurable() {
+    let menu = DynamicMenu::new("interface/wireguard").key_field("name");
+    let mut entry = menu.create();
+    assert!(entry.id_field().is_none());
+    entry.set_from_api("name", "wg0").unwrap();
+    assert_eq!(entry.id_field().map(|(name, _)| name), Some("name"));
+    entry.set_created_id("*A").unwrap();
+    assert_eq!(entry.id_field().map(|(name, _)| name), Some(".id"));
+    assert!(menu.is_key("name") && menu.is_key(".id") && !menu.is_key("mtu"));
+}
@@ -0,0 +1,208 @@
+//! Attributes of the router that do not fit the resource they are read into.
+//!
+//! Routers report attributes the model does not know after a firmware update, or values a
+//! field cannot parse, e.g. a new enum variant. In [`ParseMode::Strict`] these fail the
+//! request. In [`ParseMode::Lenient`] they are kept in the [`ExtraAttributes`] of the
+//! resource and reported as [`ParseWarning`]s, once per menu and attribute until they are
+//! taken, so long streams do not pile them up. Attributes kept this way are only sent back
+//! to the router if they were changed with [`ExtraAttributes::set`], so `set` leaves them as
+//! they are.
+
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+
+use crate::model::RouterOsResource;
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// How clients treat attributes that do not fit the resource, see the module documentation
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum ParseMode {
+    #[default]
+    Strict,
+    Lenient,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+struct ExtraAttribute {
+    name: String,
+    original: String,
+    current: String,
+}
+
+/// Attributes of an entry without matching field, or with a value the field cannot parse
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ExtraAttributes {
+    attributes: Vec<ExtraAttribute>,
+}
+
+impl ExtraAttributes {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+            .map(|attribute| attribute.current.as_str())
+    }
+    /// Changes the attribute, it is sent with the next update of the entry
+    pub fn set<V: Into<String>>(&mut self, name: &str, value: V) {
+        let value = value.into();
+        match self
+            .attributes
+            .iter_mut()
+            .find(|attribute| attribute.name == name)
+        {
+            Some(attribute) => attribute.current = value,
+            None => self.attributes.push(ExtraAttribute {
+                name: name.to_owned(),
+                original: String::new(),
+                current: value,
+            }),
+        }
+    }
+    /// Current values in the order the router reported them
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .map(|attribute| (attribute.name.as_str(), attribute.current.as_str()))
+    }
+    pub fn len(&self) -> usize {
+        self.attributes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+    pub fn is_modified(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|attribute| attribute.original != attribute.current)
+    }
+    /// Values as the router reported them, without the attributes added with
+    /// [`ExtraAttributes::set`]
+    pub fn original_values(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.attributes
+            .iter()
+            .filter(|attribute| !attribute.original.is_empty())
+            .map(|attribute| (attribute.name.as_str(), attribute.original.as_str()))
+    }
+    /// Attributes changed with [`ExtraAttributes::set`]
+    pub fn modified_values(&self) -> Vec<(&str, String)> {
+        self.attributes
+            .iter()
+            .filter(|attribute| attribute.original != attribute.current)
+            .map(|attribute| (attribute.name.as_str(), attribute.current.clone()))
+            .collect()
+    }
+    fn insert_from_api(&mut self, name: &str, value: &str) {
+        self.attributes.retain(|attribute| attribute.name != name);
+        self.attributes.push(ExtraAttribute {
+            name: name.to_owned(),
+            original: value.to_owned(),
+            current: value.to_owned(),
+        });
+    }
+}
+
+/// Attribute that was not read into a field in [`ParseMode::Lenient`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseWarning {
+    pub structure: &'static str,
+    pub attribute: String,
+    /// First value seen
+    pub value: String,
+    /// How often the attribute was not read since the warnings were last taken
+    pub count: usize,
+    /// Why the field rejected the value, `None` if the resource has no such field
+    pub error: Option<String>,
+    /// Whether the value was kept in the [`ExtraAttributes`], resources without them drop it
+    pub kept: bool,
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match &self.error {
+            Some(error) => write!(
+                f,
+                "{}: unparsable value {}={}: {error}",
+                self.structure, self.attribute, self.value
+            )?,
+            None => write!(
+                f,
+                "{}: unknown attribute {}={}",
+                self.structure, self.attribute, self.value
+            )?,
+        }
+        if !self.kept {
+            f.write_str(" (dropped)")?;
+        }
+        if self.count > 1 {
+            write!(f, ", {} times", self.count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse mode of a client, clones share the warnings
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AttributeParser {
+    pub(crate) mode: ParseMode,
+    warnings: Arc<Mutex<Vec<ParseWarning>>>,
+}
+
+impl AttributeParser {
+    /// Sets the attribute `key` of `resource` to `value` as the router reported it
+    pub(crate) fn write<R: RouterOsResource>(
+        &self,
+        resource: &mut R,
+        key: &str,
+        value: &str,
+    ) -> Result<(), RosError> {
+        let lenient = self.mode == ParseMode::Lenient;
+        let error = match resource
+            .fields_mut()
+            .find(|(description, _)| description.name == key)
+        {
+            Some((_, field)) => match field.set_from_api(value) {
+                Ok(()) => return Ok(()),
+                Err(error) if lenient => {
+                    field.set_from_api("")?;
+                    Some(error.to_string())
+                }
+                Err(error) => return Err(error),
+            },
+            None if lenient => None,
+            None => return Err(RosError::field_missing_error(key, value)),
+        };
+        let kept = match resource.extra_attributes_mut() {
+            Some(extra) => {
+                extra.insert_from_api(key, value);
+                true
+            }
+            None => false,
+        };
+        let mut warnings = self.warnings.lock().unwrap();
+        let seen = warnings.iter_mut().find(|warning| {
+            warning.structure == R::resource_path()
+                && warning.attribute == key
+                && warning.error.is_some() == error.is_some()
+        });
+        match seen {
+            Some(warning) => warning.count += 1,
+            None => warnings.push(ParseWarning {
+                structure: R::resource_path(),
+                attribute: key.to_owned(),
+                value: value.to_owned(),
+                count: 1,
+                error,
+                kept,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Warnings since the last call
+    pub(crate) fn take_warnings(&self) -> Vec<ParseWarning> {
+        std::mem::take(&mut *self.warnings.lock().unwrap())
+    }
+}
@@ -0,0 +1,86 @@
+use crate::generated::interface::bridge::port::BridgePort;
+use crate::model::extra::{AttributeParser, ParseMode};
+use crate::model::{RouterOsApiFieldAccess, RouterOsResource};
+
+fn parse(parser: &AttributeParser, attributes: &[(&str, &str)]) -> Result<BridgePort, String> {
+    let mut port = BridgePort::default();
+    for (key, value) in attributes {
+        parser
+            .write(&mut port, key, value)
+            .map_err(|error| error.to_string())?;
+    }
+    Ok(port)
+}
+
+#[test]
+fn strict_parsing_rejects_unknown_attributes_and_values() {
+    let parser = AttributeParser::default();
+    assert!(parse(&parser, &[("interface", "ether1"), ("new-flag", "yes")]).is_err());
+    assert!(parse(&parser, &[("edge", "maybe")]).is_err());
+    assert!(parser.take_warnings().is_empty());
+}
+
+#[test]
+fn lenient_parsing_keeps_unknown_attributes_and_values() {
+    let parser = AttributeParser {
+        mode: ParseMode::Lenient,
+        ..AttributeParser::default()
+    };
+    let mut port = parse(
+        &parser,
+        &[
+            ("interface", "ether1"),
+            ("new-flag", "yes"),
+            ("edge", "maybe"),
+        ],
+    )
+    .unwrap();
+    assert_eq!(port.interface.get().as_deref(), Some("ether1"));
+    assert!(port.edge.get().is_none());
+    let extra = port.extra_attributes().unwrap();
+    assert_eq!(
+        extra.iter().collect::<Vec<_>>(),
+        vec![("new-flag", "yes"), ("edge", "maybe")]
+    );
+    assert!(!port.is_modified());
+
+    let warnings = parser.take_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+        warnings[0].to_string(),
+        "interface/bridge/port: unknown attribute new-flag=yes"
+    );
+    assert!(warnings[1].error.is_some() && warnings[1].kept);
+    assert!(parser.take_warnings().is_empty());
+
+    port.extra_attributes_mut().unwrap().set("new-flag", "no");
+    assert!(port.is_modified());
+    assert_eq!(
+        port.extra_attributes().unwrap().modified_values(),
+        vec![("new-flag", String::from("no"))]
+    );
+}
+
+#[test]
+fn repeated_warnings_are_counted() {
+    let parser = AttributeParser {
+        mode: ParseMode::Lenient,
+        ..AttributeParser::default()
+    };
+    for edge in ["maybe", "perhaps", "sometimes"] {
+        parse(&parser, &[("new-flag", "yes"), ("edge", edge)]).unwrap();
+    }
+    let warnings = parser.take_warnings();
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(
+        warnings[0].to_string(),
+        "interface/bridge/port: unknown attribute new-flag=yes, 3 times"
+    );
+    assert_eq!(
+        (warnings[1].value.as_str(), warnings[1].count),
+        ("maybe", 3)
+    );
+
+    parse(&parser, &[("new-flag", "yes")]).unwrap();
+    assert_eq!(parser.take_warnings()[0].count, 1);
+}
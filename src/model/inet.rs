@@ -1,6 +1,6 @@
 use ipnet::IpNet;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
 pub struct VlanIpNetAddr {
     vlan_id: u16,
     ip: IpNet,
@@ -12,17 +12,8 @@ impl VlanIpNetAddr {
     }
 }
 
-impl Default for VlanIpNetAddr {
-    fn default() -> Self {
-        VlanIpNetAddr {
-            vlan_id: 0,
-            ip: IpNet::default(),
-        }
-    }
-}
-
-impl Into<IpNet> for VlanIpNetAddr {
-    fn into(self) -> IpNet {
-        self.ip
+impl From<VlanIpNetAddr> for IpNet {
+    fn from(value: VlanIpNetAddr) -> Self {
+        value.ip
     }
 }
@@ -33,16 +33,19 @@ pub trait RosFieldAccessor {
     fn original_value(&self, format: &ValueFormat) -> Option<String>;
     fn api_value(&self, format: &ValueFormat) -> String;
     fn set_from_api(&mut self, value: &str) -> Result<(), RosError>;
+    /// Sets the current value from its api representation, the original value is kept
+    fn set_api_value(&mut self, value: &str) -> Result<(), RosError>;
     fn clear(&mut self) -> Result<(), RosError>;
     fn reset(&mut self) -> Result<(), RosError>;
     fn has_value(&self) -> bool;
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Default)]
 pub enum Auto<V>
 where
     V: RosValue,
 {
+    #[default]
     Auto,
     Value(V),
 }
@@ -154,8 +157,8 @@ impl RosValue for u16 {
     type Err = RosError;
 
     fn from_api(value: &str) -> Result<Self::Type, Self::Err> {
-        if value.starts_with("0x") {
-            u16::from_str_radix(&value[2..], 16)
+        if let Some(hex) = value.strip_prefix("0x") {
+            u16::from_str_radix(hex, 16)
         } else {
             value.parse()
         }
@@ -172,8 +175,8 @@ impl RosValue for u8 {
     type Err = RosError;
 
     fn from_api(value: &str) -> Result<Self::Type, Self::Err> {
-        if value.starts_with("0x") {
-            u8::from_str_radix(&value[2..], 16)
+        if let Some(hex) = value.strip_prefix("0x") {
+            u8::from_str_radix(hex, 16)
         } else {
             value.parse()
         }
@@ -190,8 +193,8 @@ impl RosValue for i8 {
     type Err = RosError;
 
     fn from_api(value: &str) -> Result<Self::Type, Self::Err> {
-        if value.starts_with("0x") {
-            i8::from_str_radix(&value[2..], 16)
+        if let Some(hex) = value.strip_prefix("0x") {
+            i8::from_str_radix(hex, 16)
         } else {
             value.parse()
         }
@@ -208,8 +211,8 @@ impl RosValue for u32 {
     type Err = RosError;
 
     fn from_api(value: &str) -> Result<Self::Type, Self::Err> {
-        if value.starts_with("0x") {
-            u32::from_str_radix(&value[2..], 16)
+        if let Some(hex) = value.strip_prefix("0x") {
+            u32::from_str_radix(hex, 16)
         } else {
             value.parse()
         }
@@ -240,8 +243,8 @@ impl RosValue for Option<u32> {
     fn from_api(value: &str) -> Result<Self::Type, Self::Err> {
         if value == "none" {
             Ok(None)
-        } else if value.starts_with("0x") {
-            u32::from_str_radix(&value[2..], 16).map(Some)
+        } else if let Some(hex) = value.strip_prefix("0x") {
+            u32::from_str_radix(hex, 16).map(Some)
         } else {
             value.parse().map(Some)
         }
@@ -306,12 +309,6 @@ where
     }
 }
 
-impl<V: RosValue> Default for Auto<V> {
-    fn default() -> Self {
-        Auto::Auto
-    }
-}
-
 impl RosValue for Duration {
     type Type = Duration;
     type Err = RosError;
@@ -326,7 +323,7 @@ impl RosValue for Duration {
         let mut number = String::new();
         let mut last_was_m = false;
         for ch in value.chars() {
-            if ch.is_digit(10) {
+            if ch.is_ascii_digit() {
                 if last_was_m {
                     minute_count = number.parse()?;
                     number.clear();
@@ -358,7 +355,7 @@ impl RosValue for Duration {
             };
         }
         positional_count.reverse();
-        if let Some(count) = positional_count.get(0) {
+        if let Some(count) = positional_count.first() {
             second_count += count;
         }
         if let Some(count) = positional_count.get(1) {
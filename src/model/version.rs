@@ -0,0 +1,67 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::RosError;
+
+#[cfg(test)]
+mod tests;
+
+/// RouterOS release as reported by `version` of `/system/resource`, e.g. `7.12.1 (stable)`
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct RosVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl RosVersion {
+    pub const fn new(major: u16, minor: u16, patch: u16) -> RosVersion {
+        RosVersion {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for RosVersion {
+    type Err = RosError;
+
+    /// Parses the numeric part of a version, the channel and suffixes like `beta2` are
+    /// ignored
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || RosError::SimpleMessage(format!("Invalid RouterOS version: {value}"));
+        let mut parts = [0u16; 3];
+        let mut found = 0;
+        for part in value
+            .split_whitespace()
+            .next()
+            .unwrap_or_default()
+            .split('.')
+        {
+            let digits: String = part.chars().take_while(char::is_ascii_digit).collect();
+            if digits.is_empty() || found == parts.len() {
+                break;
+            }
+            parts[found] = digits.parse().map_err(|_| invalid())?;
+            found += 1;
+            if digits.len() < part.len() {
+                break;
+            }
+        }
+        if found == 0 {
+            return Err(invalid());
+        }
+        Ok(RosVersion::new(parts[0], parts[1], parts[2]))
+    }
+}
+
+impl Display for RosVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)?;
+        if self.patch != 0 {
+            write!(f, ".{}", self.patch)?;
+        }
+        Ok(())
+    }
+}
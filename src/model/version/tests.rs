@@ -0,0 +1,56 @@
+use crate::generated::interface::bridge::Bridge;
+use crate::generated::ip::route::Route;
+use crate::model::version::RosVersion;
+use crate::model::RouterOsApiFieldAccess;
+
+#[test]
+fn versions_are_parsed_from_resource() {
+    let version = |value: &str| value.parse::<RosVersion>().unwrap();
+    assert_eq!(version("7.12.1 (stable)"), RosVersion::new(7, 12, 1));
+    assert_eq!(version("6.49.10 (long-term)"), RosVersion::new(6, 49, 10));
+    assert_eq!(version("7.13beta2 (testing)"), RosVersion::new(7, 13, 0));
+    assert_eq!(version("7"), RosVersion::new(7, 0, 0));
+    assert!("stable".parse::<RosVersion>().is_err());
+
+    assert!(version("6.49.10") < version("7.1"));
+    assert_eq!(version("7.1").to_string(), "7.1");
+    assert_eq!(version("6.48.6").to_string(), "6.48.6");
+}
+
+#[test]
+fn fields_know_their_versions() {
+    let supported = |names: &[&str], version: RosVersion| -> Vec<bool> {
+        let route = Route::default();
+        let bridge = Bridge::default();
+        names
+            .iter()
+            .map(|name| {
+                route
+                    .fields()
+                    .chain(bridge.fields())
+                    .find(|(description, _)| description.name == *name)
+                    .unwrap()
+                    .0
+                    .is_supported_by(&version)
+            })
+            .collect()
+    };
+    let names = [
+        "routing-table",
+        "gateway-status",
+        "ingress-filtering",
+        "gateway",
+    ];
+    assert_eq!(
+        supported(&names, RosVersion::new(6, 40, 0)),
+        [false, true, false, true]
+    );
+    assert_eq!(
+        supported(&names, RosVersion::new(6, 49, 10)),
+        [false, true, true, true]
+    );
+    assert_eq!(
+        supported(&names, RosVersion::new(7, 0, 0)),
+        [true, false, true, true]
+    );
+}
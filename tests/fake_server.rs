@@ -0,0 +1,658 @@
+use std::time::Duration;
+
+use ipnet::IpNet;
+
+use routeros::client::api::fake::{FakeServer, InjectedReply};
+use routeros::client::api::record::{RecordingStream, ReplayStream};
+use routeros::client::api::{ApiClient, Transport, TrapCategory};
+use routeros::client::command::Command;
+use routeros::client::filter::{Filter, Projection};
+use routeros::client::reconnect::{ReconnectingClient, RetryPolicy};
+use routeros::client::transaction::Transaction;
+use routeros::client::version::UnsupportedFields;
+use routeros::client::{Client, ResourceAccess};
+use routeros::field_ref;
+use routeros::generated::ip::address::Address;
+use routeros::generated::ip::route::Route;
+use routeros::model::dynamic::DynamicMenu;
+use routeros::model::extra::ParseMode;
+use routeros::model::version::RosVersion;
+use routeros::model::{RosFieldValue, RouterOsApiFieldAccess, RouterOsResource};
+use routeros::RosError;
+
+async fn server() -> FakeServer {
+    let server = FakeServer::start("admin", "secret").await.unwrap();
+    server.insert(
+        "ip/address",
+        &[
+            ("address", "10.0.0.1/24"),
+            ("interface", "ether1"),
+            ("comment", "lan"),
+        ],
+    );
+    server.insert(
+        "ip/address",
+        &[("address", "192.168.88.1/24"), ("interface", "ether2")],
+    );
+    server
+}
+
+fn net(value: &str) -> IpNet {
+    value.parse().unwrap()
+}
+
+/// Resource declared outside the crate, only with the fields it needs
+#[derive(Debug, Default, Clone, RouterOsResource)]
+#[ros(path = "/ip/address")]
+struct InterfaceAddress {
+    #[ros(key, name = ".id")]
+    id: RosFieldValue<String>,
+    address: RosFieldValue<IpNet>,
+    #[ros(reference = "interface/ethernet:name")]
+    interface: RosFieldValue<String>,
+    comment: RosFieldValue<String>,
+    #[ros(read_only, dynamic)]
+    dynamic: RosFieldValue<bool>,
+}
+
+#[tokio::test]
+async fn fetch_mutate_commit() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    assert_eq!(addresses.iter().count(), 2);
+    for address in addresses.find_mut(|a| a.interface.get().as_deref() == Some("ether1")) {
+        address.comment.set("uplink");
+    }
+    addresses.remove(|a| a.interface.get().as_deref() == Some("ether2"));
+    addresses
+        .get_or_create_by_value(&field_ref!(Address => address), net("172.16.0.1/16"))
+        .interface
+        .set("bridge");
+    addresses.commit(&mut client).await.unwrap();
+
+    let mut entries = server.entries("ip/address");
+    entries.sort_by_key(|entry| entry[".id"].clone());
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["comment"], "uplink");
+    assert_eq!(entries[1][".id"], "*3");
+    assert_eq!(entries[1]["address"], "172.16.0.1/16");
+    assert_eq!(entries[1]["interface"], "bridge");
+
+    // commit reloads the entries from the router
+    let added: Vec<&Address> = addresses
+        .iter()
+        .filter(|a| a.interface.get().as_deref() == Some("bridge"))
+        .collect();
+    assert_eq!(added.len(), 1);
+    assert!(!added[0].is_modified());
+}
+
+#[tokio::test]
+async fn queries_and_proplist_are_evaluated() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    let filter = Filter::new().has_value(&field_ref!(Address => comment));
+    let projection = Projection::new().field(&field_ref!(Address => interface));
+    let addresses: Vec<Address> = client.list_projected(&filter, &projection).await.unwrap();
+    assert_eq!(addresses.len(), 1);
+    assert_eq!(addresses[0].interface.get().as_deref(), Some("ether1"));
+    assert_eq!(addresses[0].address.get(), &None);
+
+    let filter = !Filter::new().equals(&field_ref!(Address => interface), "ether1");
+    let addresses: Vec<Address> = client.list_where(&filter).await.unwrap();
+    assert_eq!(addresses.len(), 1);
+    assert_eq!(addresses[0].address.get(), &Some(net("192.168.88.1/24")));
+}
+
+#[tokio::test]
+async fn injected_replies_become_errors() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    server.inject(
+        "ip/address/add",
+        InjectedReply::Trap {
+            category: Some(1),
+            message: String::from("invalid value for argument address"),
+        },
+    );
+    let mut address = Address::default();
+    address.address.set(net("10.1.0.1/24"));
+    let error = client.add(&mut address).await.unwrap_err();
+    assert_eq!(
+        error.trap_category(),
+        Some(TrapCategory::ArgumentValueFailure)
+    );
+    assert_eq!(server.entries("ip/address").len(), 2);
+
+    server.inject(
+        "ip/address/print",
+        InjectedReply::Fatal(String::from("session terminated on request")),
+    );
+    let error = client.list::<Address>().await.unwrap_err();
+    assert!(
+        matches!(error, RosError::Fatal { message } if message == "session terminated on request")
+    );
+    assert!(client.is_closed());
+}
+
+#[tokio::test]
+async fn wrong_password_is_rejected() {
+    let server = server().await;
+    let result = ApiClient::connect(
+        server.address(),
+        String::from("admin"),
+        String::from("wrong"),
+        Transport::Plain,
+    )
+    .await;
+    assert!(matches!(result, Err(RosError::AuthenticationError { .. })));
+}
+
+#[tokio::test]
+async fn recorded_session_replays() {
+    let server = server().await;
+    let path = std::env::temp_dir().join(format!("routeros-capture-{}.jsonl", std::process::id()));
+
+    let stream = Transport::Plain.open(server.address()).await.unwrap();
+    let stream = RecordingStream::create(stream, &path).unwrap();
+    let mut client = ApiClient::from_stream(stream, String::from("admin"), String::from("secret"))
+        .await
+        .unwrap();
+    let recorded: Vec<Address> = client.list().await.unwrap();
+    drop(client);
+    drop(server);
+
+    let stream = ReplayStream::open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let mut client = ApiClient::from_stream(stream, String::from("admin"), String::from("secret"))
+        .await
+        .unwrap();
+    let replayed: Vec<Address> = client.list().await.unwrap();
+    assert_eq!(replayed.len(), recorded.len());
+    for (replayed, recorded) in replayed.iter().zip(&recorded) {
+        assert_eq!(replayed.address.get(), recorded.address.get());
+    }
+}
+
+#[tokio::test]
+async fn dynamic_menus_are_supported() {
+    let server = server().await;
+    server.insert(
+        "ip/firewall/raw",
+        &[("chain", "prerouting"), ("action", "notrack")],
+    );
+    let mut client = server.client().await.unwrap();
+    let menu = DynamicMenu::new("ip/firewall/raw");
+
+    let mut rules = client.list_dynamic(&menu).await.unwrap();
+    assert_eq!(rules.len(), 1);
+    let mut rule = rules.remove(0);
+    assert_eq!(rule.get("action"), Some("notrack"));
+    rule.set("disabled", "true");
+    client.update_dynamic(rule.clone()).await.unwrap();
+
+    let mut added = menu.create();
+    added.set("chain", "output");
+    added.set("action", "accept");
+    let id = client.add_dynamic(&mut added).await.unwrap().unwrap();
+    assert_eq!(added.get(".id"), Some(id.as_str()));
+
+    client.delete_dynamic(rule).await.unwrap();
+    let entries = server.entries("ip/firewall/raw");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0][".id"], id);
+    assert_eq!(entries[0]["chain"], "output");
+}
+
+#[tokio::test]
+async fn dynamic_entries_are_found_by_their_key() {
+    let server = server().await;
+    server.insert("interface/wireguard", &[("name", "wg0"), ("mtu", "1420")]);
+    let mut client = server.client().await.unwrap();
+    let menu = DynamicMenu::new("interface/wireguard").key_field("name");
+
+    let mut entry = menu.create();
+    entry.set_from_api("name", "wg0").unwrap();
+    entry.set("mtu", "1400");
+    client.update_dynamic(entry.clone()).await.unwrap();
+    assert_eq!(server.entries("interface/wireguard")[0]["mtu"], "1400");
+
+    assert!(client.delete_dynamic(menu.create()).await.is_err());
+    client.delete_dynamic(entry).await.unwrap();
+    assert!(server.entries("interface/wireguard").is_empty());
+}
+
+#[tokio::test]
+async fn commands_return_rows_and_value() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    let add = Command::of::<Address>("add")
+        .argument("address", net("10.9.0.1/24"))
+        .argument("interface", String::from("ether9"));
+    let output = client.execute(&add).await.unwrap();
+    assert_eq!(output.ret.as_deref(), Some("*3"));
+
+    let print = Command::new("/ip/address", "print");
+    let output = client.execute(&print).await.unwrap();
+    assert_eq!(output.rows.len(), 3);
+    assert_eq!(output.rows[2]["interface"], "ether9");
+
+    let error = client
+        .execute(&Command::new("system", "reboot"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.trap_category(),
+        Some(TrapCategory::MissingItemOrCommand)
+    );
+}
+
+#[tokio::test]
+async fn ordered_entries_are_placed_with_few_moves() {
+    let server = FakeServer::start("admin", "secret").await.unwrap();
+    for (address, interface) in [
+        ("10.0.1.1/24", "ether1"),
+        ("10.0.2.1/24", "ether2"),
+        ("10.0.3.1/24", "ether3"),
+        ("10.0.4.1/24", "ether4"),
+    ] {
+        server.insert(
+            "ip/address",
+            &[("address", address), ("interface", interface)],
+        );
+    }
+    let mut client = server.client().await.unwrap();
+
+    let mut addresses = client.fetch_ordered::<Address>().await.unwrap();
+    addresses.move_to(3, 0);
+    let mut address = Address::default();
+    address.address.set(net("10.0.9.1/24"));
+    address.interface.set("ether9");
+    addresses.insert(2, address);
+    addresses.commit(&mut client).await.unwrap();
+
+    let interfaces: Vec<String> = server
+        .entries("ip/address")
+        .iter()
+        .map(|entry| entry["interface"].clone())
+        .collect();
+    assert_eq!(
+        interfaces,
+        ["ether4", "ether1", "ether9", "ether2", "ether3"]
+    );
+    let changes: Vec<String> = server
+        .commands()
+        .into_iter()
+        .filter(|command| !command.ends_with("/print"))
+        .collect();
+    assert_eq!(changes, ["ip/address/move", "ip/address/add"]);
+    assert_eq!(addresses.len(), 5);
+}
+
+#[tokio::test]
+async fn failed_transactional_commit_restores_the_router() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    for address in addresses.find_mut(|a| a.interface.get().as_deref() == Some("ether1")) {
+        address.comment.set("uplink");
+    }
+    addresses.remove(|a| a.interface.get().as_deref() == Some("ether2"));
+    addresses
+        .get_or_create_by_value(&field_ref!(Address => address), net("172.16.0.1/16"))
+        .interface
+        .set("bridge");
+    server.inject(
+        "ip/address/add",
+        InjectedReply::Trap {
+            category: Some(1),
+            message: String::from("invalid value for argument interface"),
+        },
+    );
+    let error = addresses
+        .commit_transactional(&mut client)
+        .await
+        .unwrap_err();
+    let RosError::RolledBack { error, report } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(
+        error.trap_category(),
+        Some(TrapCategory::ArgumentValueFailure)
+    );
+    assert_eq!(report.compensated.len(), 2);
+    assert!(report.failed.is_empty());
+    assert_eq!(report.to_string(), "2 changes undone");
+
+    let entries = server.entries("ip/address");
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["interface"], "ether1");
+    assert_eq!(entries[0]["comment"], "lan");
+    assert_eq!(entries[1]["interface"], "ether2");
+    assert_eq!(entries[1]["address"], "192.168.88.1/24");
+    assert_eq!(addresses.iter().count(), 2);
+}
+
+#[tokio::test]
+async fn moved_entries_are_put_back_when_an_add_fails() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    let mut addresses = client.fetch_ordered::<Address>().await.unwrap();
+    addresses.move_to(1, 0);
+    let mut address = Address::default();
+    address.address.set(net("172.16.0.1/16"));
+    address.interface.set("bridge");
+    addresses.insert(1, address);
+    server.inject(
+        "ip/address/add",
+        InjectedReply::Trap {
+            category: None,
+            message: String::from("no such item"),
+        },
+    );
+    let error = addresses
+        .commit_transactional(&mut client)
+        .await
+        .unwrap_err();
+    let RosError::RolledBack { report, .. } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert_eq!(report.compensated.len(), 1);
+    let interfaces: Vec<String> = server
+        .entries("ip/address")
+        .iter()
+        .map(|entry| entry["interface"].clone())
+        .collect();
+    assert_eq!(interfaces, ["ether1", "ether2"]);
+
+    addresses.commit_transactional(&mut client).await.unwrap();
+}
+
+#[tokio::test]
+async fn derived_resources_are_accessible() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+
+    let resource = InterfaceAddress::default();
+    let fields: Vec<(&str, bool, bool)> = resource
+        .fields()
+        .map(|(description, _)| {
+            (
+                description.name,
+                description.is_id,
+                description.is_read_only,
+            )
+        })
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            (".id", true, false),
+            ("address", false, false),
+            ("interface", false, false),
+            ("comment", false, false),
+            ("dynamic", false, true),
+        ]
+    );
+    assert_eq!(InterfaceAddress::resource_path(), "ip/address");
+
+    let mut addresses = client.fetch::<InterfaceAddress>().await.unwrap();
+    let ether2 = addresses
+        .find_mut(|a| a.interface.get().as_deref() == Some("ether2"))
+        .remove(0);
+    assert!(!ether2.is_dynamic());
+    assert!(ether2.id.get().is_some());
+    ether2.address.set(net("192.168.89.1/24"));
+    addresses.commit(&mut client).await.unwrap();
+
+    let entries = server.entries("ip/address");
+    assert_eq!(entries[1]["address"], "192.168.89.1/24");
+    assert_eq!(entries[0]["comment"], "lan");
+}
+
+#[tokio::test]
+async fn connecting_without_access_to_the_version_succeeds() {
+    let server = server().await;
+    server.inject(
+        "system/resource/print",
+        InjectedReply::Trap {
+            category: None,
+            message: String::from("not enough permissions"),
+        },
+    );
+    let mut client = server.client().await.unwrap();
+    assert_eq!(client.version(), None);
+    assert_eq!(client.list::<Address>().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn fields_unknown_to_the_router_version_are_not_sent() {
+    let server = server().await;
+    server.insert("system/resource", &[("version", "6.49.10 (long-term)")]);
+    server.insert("ip/route", &[("dst-address", "10.1.0.0/16")]);
+    let mut client = server.client().await.unwrap();
+    assert_eq!(client.version(), Some(RosVersion::new(6, 49, 10)));
+
+    let mut routes = client.fetch::<Route>().await.unwrap();
+    for route in routes.find_mut(|_| true) {
+        route.routing_table.set("vpn");
+        route.comment.set("office");
+    }
+    let error = routes.commit(&mut client).await.unwrap_err();
+    assert!(
+        matches!(error, RosError::UnsupportedField { .. }),
+        "{error}"
+    );
+    assert!(!server.commands().contains(&String::from("ip/route/set")));
+
+    let mut client = client.unsupported_fields(UnsupportedFields::Skip);
+    routes.commit(&mut client).await.unwrap();
+    let entries = server.entries("ip/route");
+    assert_eq!(entries[0]["comment"], "office");
+    assert!(!entries[0].contains_key("routing-table"));
+}
+
+#[tokio::test]
+async fn lenient_parsing_keeps_attributes_of_newer_routers() {
+    let server = server().await;
+    server.insert(
+        "ip/address",
+        &[
+            ("address", "10.2.0.1/24"),
+            ("interface", "ether3"),
+            ("vrf", "main"),
+            ("invalid", "partially"),
+        ],
+    );
+    let mut client = server.client().await.unwrap();
+    assert!(client.list::<Address>().await.is_err());
+    assert!(client.take_warnings().is_empty());
+
+    let mut client = client.parse_mode(ParseMode::Lenient);
+    let mut addresses = client.fetch::<Address>().await.unwrap();
+    let warnings = client.take_warnings();
+    let mut messages: Vec<String> = warnings.iter().map(ToString::to_string).collect();
+    messages.sort();
+    assert_eq!(
+        messages,
+        [
+            "ip/address: unknown attribute vrf=main",
+            "ip/address: unparsable value invalid=partially: provided string was not `true` or `false`",
+        ]
+    );
+    for address in addresses.find_mut(|a| a.interface.get().as_deref() == Some("ether3")) {
+        assert_eq!(address.extra_attributes.get("vrf"), Some("main"));
+        assert_eq!(address.extra_attributes.get("invalid"), Some("partially"));
+        address.comment.set("vpn");
+    }
+    addresses.commit(&mut client).await.unwrap();
+
+    let entries = server.entries("ip/address");
+    let entry = &entries[2];
+    assert_eq!(entry["comment"], "vpn");
+    assert_eq!(entry["vrf"], "main");
+    assert_eq!(entry["invalid"], "partially");
+}
+
+#[tokio::test]
+async fn deleted_entries_are_restored_in_place() {
+    let server = FakeServer::start("admin", "secret").await.unwrap();
+    for interface in ["ether1", "ether2", "ether3"] {
+        server.insert(
+            "ip/address",
+            &[
+                ("address", "10.0.0.1/24"),
+                ("interface", interface),
+                ("vrf", "main"),
+            ],
+        );
+    }
+    let mut client = server
+        .client()
+        .await
+        .unwrap()
+        .parse_mode(ParseMode::Lenient);
+
+    let mut addresses = client.fetch_ordered::<Address>().await.unwrap();
+    addresses.remove(1);
+    addresses.move_to(1, 0);
+    server.inject(
+        "ip/address/move",
+        InjectedReply::Trap {
+            category: None,
+            message: String::from("no such item"),
+        },
+    );
+    let error = addresses
+        .commit_transactional(&mut client)
+        .await
+        .unwrap_err();
+    let RosError::RolledBack { report, .. } = error else {
+        panic!("unexpected error {error}");
+    };
+    assert!(report.failed.is_empty(), "{report}");
+    assert_eq!(report.compensated.len(), 1);
+
+    let entries = server.entries("ip/address");
+    let interfaces: Vec<&str> = entries
+        .iter()
+        .map(|entry| entry["interface"].as_str())
+        .collect();
+    assert_eq!(interfaces, ["ether1", "ether2", "ether3"]);
+    assert!(entries.iter().all(|entry| entry["vrf"] == "main"));
+}
+
+fn reconnecting_client(server: &FakeServer, keepalive: Option<Duration>) -> ReconnectingClient {
+    ReconnectingClient::new(
+        server.address(),
+        String::from("admin"),
+        String::from("secret"),
+        Transport::Plain,
+    )
+    .retry_policy(RetryPolicy {
+        max_attempts: 3,
+        initial_backoff: Duration::from_millis(10),
+        max_backoff: Duration::from_millis(10),
+    })
+    .keepalive(keepalive)
+}
+
+fn count(server: &FakeServer, command: &str) -> usize {
+    server
+        .commands()
+        .iter()
+        .filter(|executed| *executed == command)
+        .count()
+}
+
+#[tokio::test]
+async fn reads_are_retried_on_a_new_connection() {
+    let server = server().await;
+    let mut client = reconnecting_client(&server, None);
+    let first = client.client().await.unwrap();
+
+    server.inject(
+        "ip/address/print",
+        InjectedReply::Fatal(String::from("session terminated on request")),
+    );
+    let addresses: Vec<Address> = client.list().await.unwrap();
+    assert_eq!(addresses.len(), 2);
+    assert!(first.is_closed());
+    assert!(!client.client().await.unwrap().is_closed());
+}
+
+#[tokio::test]
+async fn connection_is_replaced_after_it_dropped() {
+    let server = server().await;
+    let mut client = reconnecting_client(&server, None);
+    let command = Command::new("system", "reboot");
+    server.inject(
+        "system/reboot",
+        InjectedReply::Fatal(String::from("rebooting")),
+    );
+    let error = client.execute(&command).await.unwrap_err();
+    assert!(matches!(error, RosError::Fatal { .. }));
+
+    assert_eq!(client.list::<Address>().await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn adds_are_sent_once() {
+    let server = server().await;
+    let mut client = reconnecting_client(&server, None);
+    server.inject(
+        "ip/address/add",
+        InjectedReply::Fatal(String::from("session terminated on request")),
+    );
+    let mut address = Address::default();
+    address.address.set(net("10.1.0.1/24"));
+    address.interface.set(String::from("ether3"));
+    let error = client.add(&mut address).await.unwrap_err();
+    assert!(matches!(error, RosError::Fatal { .. }));
+    assert_eq!(server.entries("ip/address").len(), 2);
+    assert_eq!(count(&server, "ip/address/add"), 0);
+
+    client.add(&mut address).await.unwrap();
+    assert_eq!(server.entries("ip/address").len(), 3);
+    assert_eq!(count(&server, "ip/address/add"), 1);
+}
+
+#[tokio::test]
+async fn keepalive_stops_when_the_client_is_dropped() {
+    let server = server().await;
+    let client = reconnecting_client(&server, Some(Duration::from_millis(20)));
+    client.client().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(count(&server, "system/identity/print") > 0);
+
+    drop(client);
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let sent = count(&server, "system/identity/print");
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(count(&server, "system/identity/print"), sent);
+}
+
+#[tokio::test]
+async fn plain_commits_are_not_journaled() {
+    let server = server().await;
+    let mut client = server.client().await.unwrap();
+    let mut addresses = client.fetch_ordered::<Address>().await.unwrap();
+    addresses.remove(0);
+    let executed = server.commands().len();
+
+    let mut transaction = Transaction::new();
+    transaction.register(&mut addresses);
+    transaction.commit(&mut client).await.unwrap();
+    // no lookups to undo the removal, only the reload after the commit
+    assert_eq!(
+        server.commands()[executed..],
+        ["ip/address/remove", "ip/address/print"]
+    );
+}